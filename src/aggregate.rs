@@ -0,0 +1,203 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::RdfJsonNodeResult;
+
+use crate::{
+    graph::{Term, Triple, RDF_LANG_STRING, XSD_STRING},
+    obj::{convert_rdf_json_node_result_to_neon_object, load_filtered_triples},
+    sparql::{numeric_value, order_terms},
+};
+
+const PARAMS_GROUP_BY: &str = "groupBy";
+
+#[derive(Clone, Copy)]
+enum GroupBy {
+    Subject,
+    Predicate,
+    Object,
+    Datatype,
+    Language,
+}
+
+impl GroupBy {
+    fn name(&self) -> &'static str {
+        match self {
+            GroupBy::Subject => "subject",
+            GroupBy::Predicate => "predicate",
+            GroupBy::Object => "object",
+            GroupBy::Datatype => "datatype",
+            GroupBy::Language => "language",
+        }
+    }
+
+    fn key(&self, triple: &Triple) -> Option<Term> {
+        match (self, &triple.object) {
+            (GroupBy::Subject, _) => Some(triple.subject.clone()),
+            (GroupBy::Predicate, _) => Some(triple.predicate.clone()),
+            (GroupBy::Object, _) => Some(triple.object.clone()),
+            (GroupBy::Datatype, Term::Literal { lang: Some(_), .. }) => {
+                Some(Term::Iri(RDF_LANG_STRING.into()))
+            }
+            (GroupBy::Datatype, Term::Literal { datatype, .. }) => Some(Term::Iri(
+                datatype.clone().unwrap_or_else(|| XSD_STRING.into()),
+            )),
+            (
+                GroupBy::Language,
+                Term::Literal {
+                    lang: Some(lang), ..
+                },
+            ) => Some(Term::Iri(lang.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&str> for GroupBy {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        [
+            GroupBy::Subject,
+            GroupBy::Predicate,
+            GroupBy::Object,
+            GroupBy::Datatype,
+            GroupBy::Language,
+        ]
+        .into_iter()
+        .find(|g| g.name() == value)
+        .ok_or_else(|| format!("cannot group by {value}"))
+    }
+}
+
+#[derive(Default)]
+struct Group {
+    count: usize,
+    subjects: HashSet<Term>,
+    predicates: HashSet<Term>,
+    objects: HashSet<Term>,
+    min: Option<Term>,
+    max: Option<Term>,
+    sum: Option<f64>,
+}
+
+impl Group {
+    fn add(&mut self, triple: Triple) {
+        self.count += 1;
+        if let Term::Literal { lang: None, .. } = &triple.object {
+            let object = Some(&triple.object);
+            if self.min.is_none() || order_terms(object, self.min.as_ref()) == Ordering::Less {
+                self.min = Some(triple.object.clone());
+            }
+            if self.max.is_none() || order_terms(object, self.max.as_ref()) == Ordering::Greater {
+                self.max = Some(triple.object.clone());
+            }
+            if let Some(value) = numeric_value(&triple.object) {
+                self.sum = Some(self.sum.unwrap_or_default() + value);
+            }
+        }
+        self.subjects.insert(triple.subject);
+        self.predicates.insert(triple.predicate);
+        self.objects.insert(triple.object);
+    }
+}
+
+pub fn aggregate(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let group_by: Vec<String> = match params.get_opt::<JsValue, _, _>(&mut cx, PARAMS_GROUP_BY)? {
+        None => vec![],
+        Some(value) => {
+            if let Ok(value) = value.downcast::<JsString, _>(&mut cx) {
+                vec![value.value(&mut cx)]
+            } else if let Ok(values) = value.downcast::<JsArray, _>(&mut cx) {
+                let mut group_by = vec![];
+                for value in values.to_vec(&mut cx)? {
+                    group_by.push(
+                        value
+                            .downcast_or_throw::<JsString, _>(&mut cx)?
+                            .value(&mut cx),
+                    );
+                }
+                group_by
+            } else {
+                return cx.throw_error(format!("'{PARAMS_GROUP_BY}' must be a string or an array"));
+            }
+        }
+    };
+    let group_by = match group_by
+        .iter()
+        .map(|g| GroupBy::try_from(g.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(group_by) => group_by,
+        Err(e) => return cx.throw_error(e),
+    };
+    let triples = match load_filtered_triples(&params, &mut cx) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+
+    // groups in order of first appearance
+    let mut keys: Vec<Vec<Option<Term>>> = vec![];
+    let mut groups: Vec<Group> = vec![];
+    let mut index = HashMap::new();
+    for triple in triples {
+        let key: Vec<Option<Term>> = group_by.iter().map(|g| g.key(&triple)).collect();
+        let idx = *index.entry(key.clone()).or_insert_with(|| {
+            keys.push(key);
+            groups.push(Group::default());
+            groups.len() - 1
+        });
+        groups[idx].add(triple);
+    }
+
+    let array = JsArray::new(&mut cx, groups.len());
+    for (idx, (key, group)) in keys.into_iter().zip(groups).enumerate() {
+        let obj = cx.empty_object();
+        let key_obj = cx.empty_object();
+        for (g, term) in group_by.iter().zip(key) {
+            let value: Handle<JsValue> = match (g, term) {
+                (_, None) => cx.null().upcast(),
+                (GroupBy::Datatype | GroupBy::Language, Some(Term::Iri(value))) => {
+                    cx.string(value).upcast()
+                }
+                (_, Some(term)) => term_to_neon_object(&mut cx, &term)?.upcast(),
+            };
+            key_obj.set(&mut cx, g.name(), value)?;
+        }
+        obj.set(&mut cx, "group", key_obj)?;
+        let count = cx.number(group.count as f64);
+        obj.set(&mut cx, "count", count)?;
+        let distinct = cx.empty_object();
+        for (name, set) in [
+            ("subjects", &group.subjects),
+            ("predicates", &group.predicates),
+            ("objects", &group.objects),
+        ] {
+            let n = cx.number(set.len() as f64);
+            distinct.set(&mut cx, name, n)?;
+        }
+        obj.set(&mut cx, "distinct", distinct)?;
+        if let Some(min) = &group.min {
+            let min = term_to_neon_object(&mut cx, min)?;
+            obj.set(&mut cx, "min", min)?;
+        }
+        if let Some(max) = &group.max {
+            let max = term_to_neon_object(&mut cx, max)?;
+            obj.set(&mut cx, "max", max)?;
+        }
+        if let Some(sum) = group.sum {
+            let sum = cx.number(sum);
+            obj.set(&mut cx, "sum", sum)?;
+        }
+        array.set(&mut cx, idx as u32, obj)?;
+    }
+    Ok(array.upcast())
+}
+
+fn term_to_neon_object<'a>(cx: &mut FunctionContext<'a>, term: &Term) -> JsResult<'a, JsObject> {
+    convert_rdf_json_node_result_to_neon_object(cx, RdfJsonNodeResult::SingleNode(term.into()))
+}
@@ -0,0 +1,74 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use neon::prelude::*;
+
+use crate::{
+    graph::{Graph, Term, Triple},
+    obj::{load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+    sparql::parse_term,
+};
+
+const PARAMS_SUBJECT: &str = "subject";
+const PARAMS_SYMMETRIC: &str = "symmetric";
+const PARAMS_MAX_DEPTH: &str = "maxDepth";
+
+/// concise bounded description: the statements of the subject and,
+/// recursively, of the blank nodes they reference. the symmetric variant also
+/// follows the statements where the node is the object.
+pub fn cbd(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let subject: String = match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_SUBJECT)? {
+        Some(subject) => subject.value(&mut cx),
+        None => return cx.throw_error(format!("missing subject ('{PARAMS_SUBJECT}')")),
+    };
+    let symmetric = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_SYMMETRIC)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let max_depth: Option<usize> = params
+        .get_opt::<JsNumber, _, _>(&mut cx, PARAMS_MAX_DEPTH)?
+        .map(|n| n.value(&mut cx).max(0.) as usize);
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let subject = match parse_term(&subject, prefixes.clone()) {
+        Ok(subject) => subject,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let graph = Graph::new(triples.iter().cloned());
+
+    let mut description: HashSet<Triple> = HashSet::new();
+    let mut visited = HashSet::from([&subject]);
+    let mut queue = VecDeque::from([(&subject, 0)]);
+    while let Some((node, depth)) = queue.pop_front() {
+        let outgoing = graph
+            .matching(Some(node), None, None)
+            .map(|t| (t, &t.object));
+        let incoming = graph
+            .matching(None, None, Some(node))
+            .filter(|_| symmetric)
+            .map(|t| (t, &t.subject));
+        for (triple, next) in outgoing.chain(incoming) {
+            description.insert(triple.clone());
+            let follow = matches!(next, Term::BlankNode(_))
+                && max_depth.map(|max| depth < max).unwrap_or(true);
+            if follow && visited.insert(next) {
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    // in the order of the model
+    let triples: Vec<Triple> = triples
+        .into_iter()
+        .filter(|t| description.remove(t))
+        .collect();
+    make_triples_response(&params, &mut cx, &triples, &prefixes)
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, HashSet};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+use crate::{
+    graph::{Graph, Term, Triple},
+    obj::{load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+    sparql::{parse_term, PathEvaluator, PropertyPath},
+};
+
+const PARAMS_PREDICATES: &str = "predicates";
+const PARAMS_ROOTS: &str = "roots";
+const PARAMS_MATERIALIZE: &str = "materialize";
+
+/// for each predicate, the triples reachable from the roots, or from every
+/// subject of the predicate when there is no root. when materialized, a
+/// triple is added from each reached node to every node reachable from it.
+pub fn closure(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let materialize = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_MATERIALIZE)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let (predicates, roots) = match read_terms(&params, &mut cx, &prefixes, PARAMS_PREDICATES)
+        .and_then(|predicates| {
+            let roots = read_terms(&params, &mut cx, &prefixes, PARAMS_ROOTS)?;
+            Ok((predicates, roots))
+        }) {
+        Ok((predicates, _)) if predicates.is_empty() => {
+            return cx.throw_error(format!("missing predicates ('{PARAMS_PREDICATES}')"))
+        }
+        Ok(terms) => terms,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let graph = Graph::new(triples.iter().cloned());
+    let evaluator = PathEvaluator::new(&graph, false);
+
+    let mut asserted = HashSet::new();
+    let mut inferred = vec![];
+    for predicate in predicates {
+        let path = PropertyPath::OneOrMore(Box::new(PropertyPath::Predicate(predicate.clone())));
+        let starts: Vec<Term> = if roots.is_empty() {
+            graph
+                .matching(None, Some(&predicate), None)
+                .map(|t| t.subject.clone())
+                .collect()
+        } else {
+            roots.clone()
+        };
+        let mut nodes = vec![];
+        let mut visited = HashSet::new();
+        for start in starts {
+            if !visited.insert(start.clone()) {
+                continue;
+            }
+            nodes.push(start.clone());
+            for node in evaluator.eval(&path, &start).nodes {
+                if visited.insert(node.clone()) {
+                    nodes.push(node);
+                }
+            }
+        }
+        for node in &nodes {
+            asserted.extend(graph.matching(Some(node), Some(&predicate), None).cloned());
+            if materialize {
+                inferred.extend(evaluator.eval(&path, node).nodes.into_iter().map(|object| {
+                    Triple {
+                        subject: node.clone(),
+                        predicate: predicate.clone(),
+                        object,
+                    }
+                }));
+            }
+        }
+    }
+    // asserted triples in the order of the model, then the inferred ones
+    let mut model: Vec<Triple> = triples.into_iter().filter(|t| asserted.remove(t)).collect();
+    let mut seen: HashSet<Triple> = model.iter().cloned().collect();
+    model.extend(inferred.into_iter().filter(|t| seen.insert(t.clone())));
+    make_triples_response(&params, &mut cx, &model, &prefixes)
+}
+
+/// a term or an array of terms, with the prefixes of the model
+fn read_terms(
+    params: &Handle<JsObject>,
+    cx: &mut FunctionContext,
+    prefixes: &BTreeMap<String, String>,
+    key: &str,
+) -> Result<Vec<Term>, TurtleDocError> {
+    let to_error = |e: neon::result::Throw| TurtleDocError {
+        message: e.to_string(),
+    };
+    let values: Vec<String> = match params.get_opt::<JsValue, _, _>(cx, key).map_err(to_error)? {
+        None => return Ok(vec![]),
+        Some(value) => {
+            if let Ok(value) = value.downcast::<JsString, _>(cx) {
+                vec![value.value(cx)]
+            } else if let Ok(values) = value.downcast::<JsArray, _>(cx) {
+                let mut strings = vec![];
+                for value in values.to_vec(cx).map_err(to_error)? {
+                    let value = value
+                        .downcast::<JsString, _>(cx)
+                        .map_err(|_| TurtleDocError {
+                            message: format!("'{key}' must be an array of strings"),
+                        })?;
+                    strings.push(value.value(cx));
+                }
+                strings
+            } else {
+                return Err(TurtleDocError {
+                    message: format!("'{key}' must be a string or an array"),
+                });
+            }
+        }
+    };
+    values
+        .iter()
+        .map(|value| parse_term(value, prefixes.clone()))
+        .collect()
+}
@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::{RdfJsonNodeResult, TurtleDocError};
+
+use crate::{
+    graph::{escape_literal, Term, Triple, XSD_STRING},
+    iri::relativize_iri,
+};
+
+const PARAMS_COMPACT_IRIS: &str = "compactIris";
+
+/// writes iris as prefixed names, using the longest namespace
+pub(crate) struct Compactor {
+    prefixes: Vec<(String, String)>,
+}
+
+impl Compactor {
+    pub(crate) fn new(prefixes: BTreeMap<String, String>) -> Self {
+        let mut prefixes: Vec<(String, String)> = prefixes
+            .into_iter()
+            .filter(|(prefix, namespace)| is_prefix(prefix) && !namespace.is_empty())
+            .collect();
+        prefixes.sort_by(|(p1, ns1), (p2, ns2)| ns2.len().cmp(&ns1.len()).then(p1.cmp(p2)));
+        Compactor { prefixes }
+    }
+
+    /// prefixed name of an iri, None when no prefix covers it
+    pub(crate) fn compact(&self, iri: &str) -> Option<String> {
+        self.prefixes.iter().find_map(|(prefix, namespace)| {
+            let local = local_name(iri.strip_prefix(namespace.as_str())?)?;
+            Some(format!("{prefix}:{local}"))
+        })
+    }
+
+    /// adds a compact field on the iri nodes of a js statement
+    pub(crate) fn compact_js_node<'a, C: Context<'a>>(
+        &self,
+        cx: &mut C,
+        obj: Handle<JsObject>,
+        node: &RdfJsonNodeResult,
+    ) -> NeonResult<()> {
+        match node {
+            RdfJsonNodeResult::SingleNode(node) if node.typ == "uri" => {
+                if let Some(compact) = self.compact(&node.value) {
+                    let compact = cx.string(compact);
+                    obj.set(cx, "compact", compact)?;
+                }
+            }
+            RdfJsonNodeResult::SingleNode(_) => {}
+            RdfJsonNodeResult::ListNodes(nodes) => {
+                let array = obj.downcast_or_throw::<JsArray, _>(cx)?;
+                for (idx, node) in nodes.iter().enumerate() {
+                    let obj: Handle<JsObject> = array.get(cx, idx as u32)?;
+                    self.compact_js_node(cx, obj, node)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// n3 serialization with the prefix declarations, iris that no prefix
+    /// covers are written relative to base when provided
+    pub(crate) fn to_n3(&self, triples: &[Triple], base: Option<&str>) -> String {
+        let mut n3 = String::new();
+        if let Some(base) = base {
+            let _ = writeln!(n3, "@base <{base}> .");
+        }
+        let mut prefixes: Vec<&(String, String)> = self.prefixes.iter().collect();
+        prefixes.sort();
+        for (prefix, namespace) in prefixes {
+            let _ = writeln!(n3, "@prefix {prefix}: <{namespace}> .");
+        }
+        for triple in triples {
+            let _ = writeln!(
+                n3,
+                "{} {} {} .",
+                self.term(&triple.subject, base),
+                self.term(&triple.predicate, base),
+                self.term(&triple.object, base)
+            );
+        }
+        n3
+    }
+
+    fn term(&self, term: &Term, base: Option<&str>) -> String {
+        match term {
+            Term::Iri(iri) => self.iri(iri, base),
+            Term::BlankNode(_) => term.to_string(),
+            Term::Literal {
+                value,
+                datatype,
+                lang,
+            } => match (lang, datatype) {
+                (Some(lang), _) => format!("\"{}\"@{lang}", escape_literal(value)),
+                (None, Some(dt)) if dt != XSD_STRING => {
+                    format!("\"{}\"^^{}", escape_literal(value), self.iri(dt, base))
+                }
+                _ => format!("\"{}\"", escape_literal(value)),
+            },
+        }
+    }
+
+    fn iri(&self, iri: &str, base: Option<&str>) -> String {
+        match (self.compact(iri), base) {
+            (Some(compact), _) => compact,
+            (None, Some(base)) => format!("<{}>", relativize_iri(iri, base)),
+            (None, None) => format!("<{iri}>"),
+        }
+    }
+}
+
+/// PN_PREFIX of turtle
+fn is_prefix(prefix: &str) -> bool {
+    prefix.is_empty()
+        || (prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+            && !prefix.ends_with('.')
+            && prefix
+                .chars()
+                .all(|c| c.is_alphanumeric() || "_-.".contains(c)))
+}
+
+/// PN_LOCAL of turtle without escapes nor dots, which don't round-trip
+/// through the parser. None when the local name cannot be written as such
+fn local_name(local: &str) -> Option<&str> {
+    let mut chars = local.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {
+                let hex = [chars.next()?, chars.next()?];
+                if !hex.iter().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+            }
+            c if c.is_alphanumeric() || "_:-".contains(c) => {}
+            _ => return None,
+        }
+    }
+    (!local.starts_with('-')).then_some(local)
+}
+
+/// a compactor over the prefixes of the models and extraPrefixes when
+/// compactIris is set
+pub(crate) fn read_compactor<'a, C: Context<'a>>(
+    params: &Handle<JsObject>,
+    cx: &mut C,
+    prefixes: &BTreeMap<String, String>,
+) -> Result<Option<Compactor>, TurtleDocError> {
+    let compact_iris = params
+        .get_opt::<JsBoolean, _, _>(cx, PARAMS_COMPACT_IRIS)
+        .map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?
+        .map(|b| b.value(cx))
+        .unwrap_or(false);
+    if !compact_iris {
+        return Ok(None);
+    }
+    Ok(Some(Compactor::new(prefixes.clone())))
+}
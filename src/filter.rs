@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, HashSet};
+
+use neon::prelude::*;
+use regex::Regex;
+use tortank::turtle::turtle_doc::{RdfJsonNode, RdfJsonNodeResult, RdfJsonTriple, TurtleDocError};
+
+use crate::{
+    graph::{extended_lang_matches, Term, RDF_LANG_STRING, XSD_STRING},
+    sparql::{compile_regex, parse_term},
+};
+
+const PARAMS_SUBJECT_NODE: &str = "subject";
+const PARAMS_PREDICATE_NODE: &str = "predicate";
+const PARAMS_OBJECT_NODE: &str = "object";
+const PARAMS_LANG: &str = "lang";
+const PARAMS_DATATYPE: &str = "datatype";
+const PARAMS_REGEX: &str = "regex";
+const PARAMS_FLAGS: &str = "flags";
+const PARAMS_PREFIX: &str = "prefix";
+const PARAMS_CONTAINS: &str = "contains";
+const PARAMS_NOT: &str = "not";
+
+/// prefixes a datatype filter can use even when the model doesn't declare them
+const DEFAULT_PREFIXES: [(&str, &str); 2] = [
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+];
+
+/// filter on a position of a statement. an exact value is resolved by
+/// tortank, pattern filters are evaluated on the value of the node, e.g the
+/// iri, the lexical form of a literal or the label of a blank node.
+enum ValueFilter {
+    Exact(String),
+    Regex(Regex),
+    Prefix(String),
+    Contains(String),
+    In(HashSet<Term>),
+    Not(Box<ValueFilter>),
+}
+
+impl ValueFilter {
+    fn exact(&self) -> Option<String> {
+        match self {
+            ValueFilter::Exact(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, node: &RdfJsonNode) -> bool {
+        match self {
+            ValueFilter::Exact(_) => true,
+            ValueFilter::Regex(regex) => regex.is_match(&node.value),
+            ValueFilter::Prefix(prefix) => node.value.starts_with(prefix.as_str()),
+            ValueFilter::Contains(needle) => node.value.contains(needle.as_str()),
+            ValueFilter::In(terms) => Term::try_from(node)
+                .map(|term| terms.contains(&term))
+                .unwrap_or(false),
+            ValueFilter::Not(filter) => !filter.matches(node),
+        }
+    }
+}
+
+/// filters of statements and aggregate
+#[derive(Default)]
+pub(crate) struct StatementFilter {
+    subject: Option<ValueFilter>,
+    predicate: Option<ValueFilter>,
+    object: Option<ValueFilter>,
+    lang: Option<String>,
+    datatype: Option<String>,
+}
+
+impl StatementFilter {
+    /// prefixes are the ones of the model, used to resolve prefixed names
+    pub(crate) fn read<'a, C: Context<'a>>(
+        params: &Handle<JsObject>,
+        cx: &mut C,
+        prefixes: &BTreeMap<String, String>,
+    ) -> Result<StatementFilter, TurtleDocError> {
+        let prefixes: BTreeMap<String, String> = DEFAULT_PREFIXES
+            .iter()
+            .map(|(p, iri)| (p.to_string(), iri.to_string()))
+            .chain(prefixes.clone())
+            .collect();
+        let lang = read_string(params, cx, PARAMS_LANG)?;
+        let datatype = read_string(params, cx, PARAMS_DATATYPE)?
+            .map(|datatype| resolve_datatype(&prefixes, datatype));
+        let mut read = |key: &str| -> Result<Option<ValueFilter>, TurtleDocError> {
+            match params.get_opt::<JsValue, _, _>(cx, key).map_err(to_error)? {
+                Some(value) => read_value_filter(cx, &prefixes, key, value, false).map(Some),
+                None => Ok(None),
+            }
+        };
+        Ok(StatementFilter {
+            subject: read(PARAMS_SUBJECT_NODE)?,
+            predicate: read(PARAMS_PREDICATE_NODE)?,
+            object: read(PARAMS_OBJECT_NODE)?,
+            lang,
+            datatype,
+        })
+    }
+
+    /// the subject, predicate and object values resolved by tortank
+    pub(crate) fn exact(&self) -> (Option<String>, Option<String>, Option<String>) {
+        let exact = |filter: &Option<ValueFilter>| filter.as_ref().and_then(ValueFilter::exact);
+        (
+            exact(&self.subject),
+            exact(&self.predicate),
+            exact(&self.object),
+        )
+    }
+
+    /// true if nothing is left to check after the exact values
+    pub(crate) fn is_exact(&self) -> bool {
+        self.lang.is_none()
+            && self.datatype.is_none()
+            && [&self.subject, &self.predicate, &self.object]
+                .into_iter()
+                .all(|f| f.as_ref().map(|f| f.exact().is_some()).unwrap_or(true))
+    }
+
+    pub(crate) fn matches(&self, triple: &RdfJsonTriple) -> bool {
+        let (
+            RdfJsonNodeResult::SingleNode(subject),
+            RdfJsonNodeResult::SingleNode(predicate),
+            RdfJsonNodeResult::SingleNode(object),
+        ) = (&triple.subject, &triple.predicate, &triple.object)
+        else {
+            return false;
+        };
+        let matches = |filter: &Option<ValueFilter>, node: &RdfJsonNode| {
+            filter.as_ref().map(|f| f.matches(node)).unwrap_or(true)
+        };
+        matches(&self.subject, subject)
+            && matches(&self.predicate, predicate)
+            && matches(&self.object, object)
+            && self
+                .lang
+                .as_ref()
+                .map(|range| match &object.lang {
+                    Some(lang) => extended_lang_matches(lang, range),
+                    None => false,
+                })
+                .unwrap_or(true)
+            && self
+                .datatype
+                .as_ref()
+                .map(|datatype| datatype_of(object) == Some(datatype.as_str()))
+                .unwrap_or(true)
+    }
+}
+
+fn datatype_of(node: &RdfJsonNode) -> Option<&str> {
+    if node.typ != "literal" {
+        return None;
+    }
+    match (&node.lang, &node.datatype) {
+        (Some(_), _) => Some(RDF_LANG_STRING),
+        (None, Some(datatype)) => Some(datatype.as_str()),
+        (None, None) => Some(XSD_STRING),
+    }
+}
+
+/// <iri>, a prefixed name or an absolute iri
+fn resolve_datatype(prefixes: &BTreeMap<String, String>, datatype: String) -> String {
+    if let Some(iri) = datatype.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+        return iri.to_string();
+    }
+    let Some((prefix, local)) = datatype.split_once(':') else {
+        return datatype;
+    };
+    if local.starts_with("//") {
+        return datatype;
+    }
+    match prefixes.get(prefix) {
+        Some(iri) => format!("{iri}{local}"),
+        None => datatype,
+    }
+}
+
+fn to_error(e: neon::result::Throw) -> TurtleDocError {
+    TurtleDocError {
+        message: e.to_string(),
+    }
+}
+
+fn read_string<'a, C: Context<'a>>(
+    params: &Handle<JsObject>,
+    cx: &mut C,
+    key: &str,
+) -> Result<Option<String>, TurtleDocError> {
+    Ok(params
+        .get_opt::<JsString, _, _>(cx, key)
+        .map_err(to_error)?
+        .map(|v| v.value(cx)))
+}
+
+/// a string, an array of strings (set membership), a pattern object or a
+/// not object. inside an array or a not, strings are resolved to terms so
+/// they can be compared without tortank
+fn read_value_filter<'a, C: Context<'a>>(
+    cx: &mut C,
+    prefixes: &BTreeMap<String, String>,
+    key: &str,
+    value: Handle<JsValue>,
+    nested: bool,
+) -> Result<ValueFilter, TurtleDocError> {
+    if let Ok(value) = value.downcast::<JsString, _>(cx) {
+        let value = value.value(cx);
+        if !nested {
+            return Ok(ValueFilter::Exact(value));
+        }
+        let term = parse_term(&value, prefixes.clone())?;
+        return Ok(ValueFilter::In(HashSet::from([term])));
+    }
+    if let Ok(values) = value.downcast::<JsArray, _>(cx) {
+        let mut terms = HashSet::new();
+        for value in values.to_vec(cx).map_err(to_error)? {
+            let value = value
+                .downcast::<JsString, _>(cx)
+                .map_err(|_| TurtleDocError {
+                    message: format!("'{key}' must be an array of strings"),
+                })?
+                .value(cx);
+            terms.insert(parse_term(&value, prefixes.clone())?);
+        }
+        return Ok(ValueFilter::In(terms));
+    }
+    let Ok(value) = value.downcast::<JsObject, _>(cx) else {
+        return Err(TurtleDocError {
+            message: format!("'{key}' must be a string, an array or an object"),
+        });
+    };
+    if let Some(not) = value
+        .get_opt::<JsValue, _, _>(cx, PARAMS_NOT)
+        .map_err(to_error)?
+    {
+        let filter = read_value_filter(cx, prefixes, key, not, true)?;
+        return Ok(ValueFilter::Not(Box::new(filter)));
+    }
+    if let Some(regex) = read_string(&value, cx, PARAMS_REGEX)? {
+        let flags = read_string(&value, cx, PARAMS_FLAGS)?.unwrap_or_default();
+        return compile_regex(&regex, &flags)
+            .map(ValueFilter::Regex)
+            .map_err(|e| TurtleDocError {
+                message: format!("'{key}': {e}"),
+            });
+    }
+    if let Some(prefix) = read_string(&value, cx, PARAMS_PREFIX)? {
+        return Ok(ValueFilter::Prefix(prefix));
+    }
+    if let Some(contains) = read_string(&value, cx, PARAMS_CONTAINS)? {
+        return Ok(ValueFilter::Contains(contains));
+    }
+    Err(TurtleDocError {
+        message: format!(
+            "'{key}' must have one of '{PARAMS_NOT}', '{PARAMS_REGEX}', '{PARAMS_PREFIX}' or '{PARAMS_CONTAINS}'"
+        ),
+    })
+}
@@ -0,0 +1,511 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use sha2::{Digest, Sha256};
+use tortank::turtle::turtle_doc::{RdfJsonNode, RdfJsonNodeResult, RdfJsonTriple, TurtleDocError};
+
+pub const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+pub const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+pub const XSD_DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
+pub const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+pub const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+pub const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub const RDF_LANG_STRING: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+
+const TYPE_URI: &str = "uri";
+const TYPE_BNODE: &str = "bnode";
+const TYPE_LITERAL: &str = "literal";
+
+/// owned rdf term, independent of the lifetime of the parsed document
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Term {
+    Iri(String),
+    BlankNode(String),
+    Literal {
+        value: String,
+        datatype: Option<String>,
+        lang: Option<String>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Triple {
+    pub subject: Term,
+    pub predicate: Term,
+    pub object: Term,
+}
+
+impl TryFrom<&RdfJsonNode> for Term {
+    type Error = TurtleDocError;
+
+    fn try_from(node: &RdfJsonNode) -> Result<Self, Self::Error> {
+        match node.typ.as_str() {
+            TYPE_URI => Ok(Term::Iri(node.value.clone())),
+            TYPE_BNODE => Ok(Term::BlankNode(node.value.clone())),
+            TYPE_LITERAL => {
+                // plain literals are xsd:string, make it explicit so
+                // literals coming from js and from turtle compare equal
+                let datatype = match (&node.datatype, &node.lang) {
+                    (None, None) => Some(XSD_STRING.to_string()),
+                    (dt, _) => dt.clone(),
+                };
+                Ok(Term::Literal {
+                    value: node.value.clone(),
+                    datatype,
+                    lang: node.lang.clone(),
+                })
+            }
+            t => Err(TurtleDocError {
+                message: format!("type {t} unknown"),
+            }),
+        }
+    }
+}
+
+impl From<&Term> for RdfJsonNode {
+    fn from(term: &Term) -> Self {
+        match term {
+            Term::Iri(iri) => RdfJsonNode {
+                typ: TYPE_URI.into(),
+                datatype: None,
+                lang: None,
+                value: iri.clone(),
+            },
+            Term::BlankNode(label) => RdfJsonNode {
+                typ: TYPE_BNODE.into(),
+                datatype: None,
+                lang: None,
+                value: label.clone(),
+            },
+            Term::Literal {
+                value,
+                datatype,
+                lang,
+            } => RdfJsonNode {
+                typ: TYPE_LITERAL.into(),
+                datatype: datatype.clone(),
+                lang: lang.clone(),
+                value: value.clone(),
+            },
+        }
+    }
+}
+
+impl From<&Triple> for RdfJsonTriple {
+    fn from(triple: &Triple) -> Self {
+        RdfJsonTriple {
+            subject: RdfJsonNodeResult::SingleNode((&triple.subject).into()),
+            predicate: RdfJsonNodeResult::SingleNode((&triple.predicate).into()),
+            object: RdfJsonNodeResult::SingleNode((&triple.object).into()),
+        }
+    }
+}
+
+fn single_term(node: &RdfJsonNodeResult, position: &str) -> Result<Term, TurtleDocError> {
+    match node {
+        RdfJsonNodeResult::SingleNode(node) => node.try_into(),
+        RdfJsonNodeResult::ListNodes(_) => Err(TurtleDocError {
+            message: format!("a list is not allowed as {position}"),
+        }),
+    }
+}
+
+fn flatten_terms(node: &RdfJsonNodeResult, out: &mut Vec<Term>) -> Result<(), TurtleDocError> {
+    match node {
+        RdfJsonNodeResult::SingleNode(node) => out.push(node.try_into()?),
+        RdfJsonNodeResult::ListNodes(nodes) => {
+            for node in nodes {
+                flatten_terms(node, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// converts rdf json triples to owned triples. an object list is expanded to
+/// one triple per object, the same way the turtle parser does it.
+pub fn triples_from_rdf_json(triples: &[RdfJsonTriple]) -> Result<Vec<Triple>, TurtleDocError> {
+    let mut out = Vec::with_capacity(triples.len());
+    let mut seen = BTreeSet::new();
+    for triple in triples {
+        let subject = single_term(&triple.subject, "subject")?;
+        let predicate = single_term(&triple.predicate, "predicate")?;
+        let mut objects = vec![];
+        flatten_terms(&triple.object, &mut objects)?;
+        for object in objects {
+            let triple = Triple {
+                subject: subject.clone(),
+                predicate: predicate.clone(),
+                object,
+            };
+            if seen.insert(triple.clone()) {
+                out.push(triple);
+            }
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn escape_literal(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// N-Triples serialization of a term
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Iri(iri) => write!(f, "<{iri}>"),
+            Term::BlankNode(label) => write!(f, "_:{label}"),
+            Term::Literal {
+                value,
+                datatype,
+                lang,
+            } => {
+                write!(f, "\"{}\"", escape_literal(value))?;
+                if let Some(lang) = lang {
+                    write!(f, "@{lang}")
+                } else if let Some(dt) = datatype.as_ref().filter(|dt| dt.as_str() != XSD_STRING) {
+                    write!(f, "^^<{dt}>")
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// N-Triples serialization of a triple, without the trailing newline
+impl Display for Triple {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {} .", self.subject, self.predicate, self.object)
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// relabels blank nodes deterministically, so two isomorphic graphs end up
+/// with the same labels. each blank node gets a hash of its direct
+/// neighbourhood, which is then refined with the hashes of its neighbours
+/// until the partition of its connected blank nodes is stable. labels are
+/// taken from the hashes, so a blank node keeps its label when unrelated
+/// blank nodes are added or removed.
+pub fn canonicalize_blank_nodes(triples: &[Triple]) -> Vec<Triple> {
+    let mut neighbourhoods: BTreeMap<&str, Vec<&Triple>> = BTreeMap::new();
+    for triple in triples {
+        for term in [&triple.subject, &triple.object] {
+            if let Term::BlankNode(label) = term {
+                let neighbourhood = neighbourhoods.entry(label.as_str()).or_default();
+                if !neighbourhood
+                    .last()
+                    .is_some_and(|t| std::ptr::eq(*t, triple))
+                {
+                    neighbourhood.push(triple);
+                }
+            }
+        }
+    }
+    if neighbourhoods.is_empty() {
+        return triples.to_vec();
+    }
+
+    let render = |term: &Term, me: &str, colors: &BTreeMap<&str, String>| match term {
+        Term::BlankNode(label) if label == me => "_:a".to_string(),
+        Term::BlankNode(label) => format!("_:{}", colors[label.as_str()]),
+        term => term.to_string(),
+    };
+
+    let mut colors: BTreeMap<&str, String> = BTreeMap::new();
+    for component in blank_node_components(&neighbourhoods) {
+        let mut component_colors: BTreeMap<&str, String> = component
+            .iter()
+            .map(|label| (*label, String::new()))
+            .collect();
+        let mut distinct = 0;
+        for _ in 0..=component.len() {
+            let mut next = BTreeMap::new();
+            for (label, color) in component_colors.iter() {
+                let mut lines = neighbourhoods[label]
+                    .iter()
+                    .map(|t| {
+                        format!(
+                            "{} {} {}",
+                            render(&t.subject, label, &component_colors),
+                            t.predicate,
+                            render(&t.object, label, &component_colors)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                lines.sort();
+                next.insert(
+                    *label,
+                    sha256_hex(format!("{color}\n{}", lines.join("\n")).as_bytes()),
+                );
+            }
+            component_colors = next;
+            let count = component_colors.values().collect::<BTreeSet<_>>().len();
+            if count == distinct {
+                break;
+            }
+            distinct = count;
+        }
+        colors.extend(component_colors);
+    }
+
+    // nodes with the same hash, e.g copies of the same anonymous node, are
+    // numbered in the order of their original labels
+    let mut ordered = colors.iter().collect::<Vec<_>>();
+    ordered.sort_by(|(l1, c1), (l2, c2)| c1.cmp(c2).then(l1.cmp(l2)));
+    let mut labels: BTreeMap<&str, String> = BTreeMap::new();
+    let mut previous: Option<(&str, usize)> = None;
+    for (label, color) in ordered {
+        let hash = &color[..16];
+        let copy = match previous {
+            Some((p, n)) if p == hash => n + 1,
+            _ => 0,
+        };
+        previous = Some((hash, copy));
+        let canonical = if copy == 0 {
+            format!("c14n{hash}")
+        } else {
+            format!("c14n{hash}_{copy}")
+        };
+        labels.insert(*label, canonical);
+    }
+    let relabel = |term: &Term| match term {
+        Term::BlankNode(label) => Term::BlankNode(labels[label.as_str()].clone()),
+        term => term.clone(),
+    };
+    triples
+        .iter()
+        .map(|t| Triple {
+            subject: relabel(&t.subject),
+            predicate: t.predicate.clone(),
+            object: relabel(&t.object),
+        })
+        .collect()
+}
+
+/// blank nodes grouped by the statements that link them together
+fn blank_node_components<'a>(
+    neighbourhoods: &BTreeMap<&'a str, Vec<&Triple>>,
+) -> Vec<Vec<&'a str>> {
+    let mut components = vec![];
+    let mut visited: HashSet<&str> = HashSet::new();
+    for start in neighbourhoods.keys() {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut component = vec![*start];
+        let mut idx = 0;
+        while idx < component.len() {
+            for triple in &neighbourhoods[component[idx]] {
+                for term in [&triple.subject, &triple.object] {
+                    if let Term::BlankNode(label) = term {
+                        let (label, _) = neighbourhoods
+                            .get_key_value(label.as_str())
+                            .expect("every blank node has a neighbourhood");
+                        if visited.insert(label) {
+                            component.push(label);
+                        }
+                    }
+                }
+            }
+            idx += 1;
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// renames the blank nodes of each model that are already used by a previous
+/// model, so unrelated anonymous nodes are not fused when the models are merged
+pub fn standardize_apart(models: Vec<Vec<Triple>>) -> Vec<Vec<Triple>> {
+    let labels = |model: &[Triple]| -> BTreeSet<String> {
+        model
+            .iter()
+            .flat_map(|t| [&t.subject, &t.object])
+            .filter_map(|term| match term {
+                Term::BlankNode(label) => Some(label.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    let mut used: BTreeSet<String> = models.iter().flat_map(|m| labels(m)).collect();
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut out = Vec::with_capacity(models.len());
+    for (idx, model) in models.into_iter().enumerate() {
+        let model_labels = labels(&model);
+        let mut renamed: BTreeMap<String, String> = BTreeMap::new();
+        for label in model_labels.intersection(&seen) {
+            let mut fresh = format!("{label}_{idx}");
+            while used.contains(&fresh) {
+                fresh.push('_');
+            }
+            used.insert(fresh.clone());
+            renamed.insert(label.clone(), fresh);
+        }
+        seen.extend(model_labels);
+        seen.extend(renamed.values().cloned());
+        if renamed.is_empty() {
+            out.push(model);
+            continue;
+        }
+        let rename = |term: Term| match term {
+            Term::BlankNode(label) if renamed.contains_key(&label) => {
+                Term::BlankNode(renamed[&label].clone())
+            }
+            term => term,
+        };
+        out.push(
+            model
+                .into_iter()
+                .map(|t| Triple {
+                    subject: rename(t.subject),
+                    predicate: t.predicate,
+                    object: rename(t.object),
+                })
+                .collect(),
+        );
+    }
+    out
+}
+
+/// triples indexed by subject, predicate and object
+#[derive(Default)]
+pub struct Graph {
+    triples: Vec<Triple>,
+    set: HashSet<Triple>,
+    by_subject: HashMap<Term, Vec<usize>>,
+    by_predicate: HashMap<Term, Vec<usize>>,
+    by_object: HashMap<Term, Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new(triples: impl IntoIterator<Item = Triple>) -> Graph {
+        let mut graph = Graph::default();
+        for triple in triples {
+            graph.insert(triple);
+        }
+        graph
+    }
+
+    /// returns false if the triple was already in the graph
+    pub fn insert(&mut self, triple: Triple) -> bool {
+        if self.set.contains(&triple) {
+            return false;
+        }
+        let idx = self.triples.len();
+        self.by_subject
+            .entry(triple.subject.clone())
+            .or_default()
+            .push(idx);
+        self.by_predicate
+            .entry(triple.predicate.clone())
+            .or_default()
+            .push(idx);
+        self.by_object
+            .entry(triple.object.clone())
+            .or_default()
+            .push(idx);
+        self.set.insert(triple.clone());
+        self.triples.push(triple);
+        true
+    }
+
+    pub fn into_triples(self) -> Vec<Triple> {
+        self.triples
+    }
+
+    /// triples matching the given positions, None matching anything
+    pub fn matching<'a>(
+        &'a self,
+        subject: Option<&'a Term>,
+        predicate: Option<&'a Term>,
+        object: Option<&'a Term>,
+    ) -> Box<dyn Iterator<Item = &'a Triple> + 'a> {
+        let candidates = [
+            subject.map(|s| self.by_subject.get(s)),
+            predicate.map(|p| self.by_predicate.get(p)),
+            object.map(|o| self.by_object.get(o)),
+        ];
+        let mut smallest: Option<&Vec<usize>> = None;
+        for candidate in candidates.into_iter().flatten() {
+            let Some(candidate) = candidate else {
+                return Box::new(std::iter::empty());
+            };
+            if smallest.map(|s| candidate.len() < s.len()).unwrap_or(true) {
+                smallest = Some(candidate);
+            }
+        }
+        let matches = move |t: &&Triple| {
+            subject.map(|s| &t.subject == s).unwrap_or(true)
+                && predicate.map(|p| &t.predicate == p).unwrap_or(true)
+                && object.map(|o| &t.object == o).unwrap_or(true)
+        };
+        match smallest {
+            Some(indexes) => Box::new(
+                indexes
+                    .iter()
+                    .map(move |idx| &self.triples[*idx])
+                    .filter(matches),
+            ),
+            None => Box::new(self.triples.iter()),
+        }
+    }
+}
+
+/// basic language range matching (RFC 4647), "*" matches any language tag
+pub fn lang_matches(tag: &str, range: &str) -> bool {
+    if range == "*" {
+        return !tag.is_empty();
+    }
+    let tag = tag.to_ascii_lowercase();
+    let range = range.to_ascii_lowercase();
+    tag == range || (tag.starts_with(&range) && tag[range.len()..].starts_with('-'))
+}
+
+/// extended language range matching (RFC 4647 3.3.2), e.g "en-*" or "*-CH"
+pub fn extended_lang_matches(tag: &str, range: &str) -> bool {
+    let tag: Vec<String> = tag.split('-').map(str::to_ascii_lowercase).collect();
+    let range: Vec<String> = range.split('-').map(str::to_ascii_lowercase).collect();
+    if tag[0].is_empty() || (range[0] != "*" && range[0] != tag[0]) {
+        return false;
+    }
+    let mut t = 1;
+    for subtag in &range[1..] {
+        if subtag == "*" {
+            continue;
+        }
+        loop {
+            match tag.get(t) {
+                None => return false,
+                Some(s) if s == subtag => {
+                    t += 1;
+                    break;
+                }
+                // a singleton ends the subtags a wildcard can skip
+                Some(s) if s.len() == 1 => return false,
+                Some(_) => t += 1,
+            }
+        }
+    }
+    true
+}
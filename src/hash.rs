@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use neon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    graph::{canonicalize_blank_nodes, sha256_hex, to_hex, Triple},
+    obj::{
+        has_input, load_triples, PARAMS_LHS_DATA, PARAMS_LHS_PATH, PARAMS_RHS_DATA, PARAMS_RHS_PATH,
+    },
+};
+
+const PARAMS_HASH_ALGORITHM: &str = "hashAlgorithm";
+const PARAMS_PREVIOUS_HASH: &str = "previousHash";
+
+const HASH_ALGORITHM_SHA256: &str = "sha256";
+const HASH_ALGORITHM_MULTISET: &str = "multiset";
+
+pub fn hash(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let algorithm: String = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_HASH_ALGORITHM)?
+        .map(|a| a.value(&mut cx))
+        .unwrap_or_else(|| HASH_ALGORITHM_SHA256.into());
+    let previous_hash: Option<String> = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_PREVIOUS_HASH)?
+        .map(|h| h.value(&mut cx));
+
+    let lhs = match load_triples(
+        &params,
+        &mut cx,
+        &mut BTreeMap::new(),
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+
+    let digest = match algorithm.as_str() {
+        HASH_ALGORITHM_SHA256 => canonical_hash(&lhs),
+        HASH_ALGORITHM_MULTISET => {
+            let removed = if has_input(&params, &mut cx, PARAMS_RHS_PATH, PARAMS_RHS_DATA)? {
+                match load_triples(
+                    &params,
+                    &mut cx,
+                    &mut BTreeMap::new(),
+                    PARAMS_RHS_PATH,
+                    PARAMS_RHS_DATA,
+                ) {
+                    Ok(triples) => triples,
+                    Err(e) => return cx.throw_error(e.message),
+                }
+            } else {
+                vec![]
+            };
+            let previous = match previous_hash.map(|h| parse_digest(&h)).transpose() {
+                Ok(previous) => previous.unwrap_or_default(),
+                Err(e) => return cx.throw_error(e),
+            };
+            to_hex(&multiset_hash(previous, &lhs, &removed))
+        }
+        a => return cx.throw_error(format!("unknown hash algorithm {a}")),
+    };
+    Ok(cx.string(digest).upcast())
+}
+
+/// sha-256 over the sorted, canonical N-Triples serialization of the model
+fn canonical_hash(triples: &[Triple]) -> String {
+    let mut lines = canonicalize_blank_nodes(triples)
+        .iter()
+        .map(|t| format!("{t}\n"))
+        .collect::<Vec<_>>();
+    lines.sort();
+    sha256_hex(lines.concat().as_bytes())
+}
+
+/// order independent hash: the sum, modulo 2^256, of the sha-256 of each
+/// triple. added triples are summed to the previous digest and removed
+/// triples are subtracted, so a digest can be maintained without reloading
+/// the whole model.
+fn multiset_hash(previous: [u8; 32], added: &[Triple], removed: &[Triple]) -> [u8; 32] {
+    let mut acc = previous;
+    for triple in canonicalize_blank_nodes(added) {
+        add_assign(
+            &mut acc,
+            &Sha256::digest(format!("{triple}\n").as_bytes()).into(),
+        );
+    }
+    for triple in canonicalize_blank_nodes(removed) {
+        sub_assign(
+            &mut acc,
+            &Sha256::digest(format!("{triple}\n").as_bytes()).into(),
+        );
+    }
+    acc
+}
+
+fn add_assign(acc: &mut [u8; 32], value: &[u8; 32]) {
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let sum = acc[i] as u16 + value[i] as u16 + carry;
+        acc[i] = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+fn sub_assign(acc: &mut [u8; 32], value: &[u8; 32]) {
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = acc[i] as i16 - value[i] as i16 - borrow;
+        borrow = if diff < 0 {
+            diff += 256;
+            1
+        } else {
+            0
+        };
+        acc[i] = diff as u8;
+    }
+}
+
+fn parse_digest(hex: &str) -> Result<[u8; 32], String> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(format!(
+            "{PARAMS_PREVIOUS_HASH} must be a 64 characters hex string"
+        ));
+    }
+    let mut digest = [0u8; 32];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| e.to_string())?;
+    }
+    Ok(digest)
+}
@@ -0,0 +1,438 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use neon::prelude::*;
+use regex::Regex;
+use tortank::turtle::turtle_doc::{RdfJsonNode, RdfJsonNodeResult, RdfJsonTriple, TurtleDocError};
+
+use crate::{
+    graph::{Term, Triple},
+    obj::{convert_rdf_json_triple_to_neon_object, load_triples, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+};
+
+const PARAMS_IRI_MODE: &str = "iriMode";
+
+const IRI_MODE_STRICT: &str = "strict";
+const IRI_MODE_NORMALIZE: &str = "normalize";
+const IRI_MODE_LENIENT: &str = "lenient";
+
+const UCSCHAR: &str = r"\u{A0}-\u{D7FF}\u{F900}-\u{FDCF}\u{FDF0}-\u{FFEF}\u{10000}-\u{1FFFD}\u{20000}-\u{2FFFD}\u{30000}-\u{3FFFD}\u{40000}-\u{4FFFD}\u{50000}-\u{5FFFD}\u{60000}-\u{6FFFD}\u{70000}-\u{7FFFD}\u{80000}-\u{8FFFD}\u{90000}-\u{9FFFD}\u{A0000}-\u{AFFFD}\u{B0000}-\u{BFFFD}\u{C0000}-\u{CFFFD}\u{D0000}-\u{DFFFD}\u{E1000}-\u{EFFFD}";
+const IPRIVATE: &str = r"\u{E000}-\u{F8FF}\u{F0000}-\u{FFFFD}\u{100000}-\u{10FFFD}";
+const SUB_DELIMS: &str = r"!$&'()*+,;=";
+
+/// characters allowed anywhere in an iri, the structure is checked by IRI
+static IRI_CHARS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"[^A-Za-z0-9\-._~{UCSCHAR}{IPRIVATE}{SUB_DELIMS}:/?#\[\]@%]"
+    ))
+    .unwrap()
+});
+static PERCENT_ENCODING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"%(?:[0-9A-Fa-f]{2})?").unwrap());
+static SCHEME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9+\-.]*:").unwrap());
+/// IRI production of rfc 3987, section 2.2
+static IRI: LazyLock<Regex> = LazyLock::new(|| {
+    let unreserved = format!(r"A-Za-z0-9\-._~{UCSCHAR}");
+    let pct = "%[0-9A-Fa-f]{2}";
+    let ipchar = format!("(?:[{unreserved}{SUB_DELIMS}:@]|{pct})");
+    Regex::new(&format!(
+        r"^[A-Za-z][A-Za-z0-9+\-.]*:(?://(?:(?:[{unreserved}{SUB_DELIMS}:]|{pct})*@)?(?:\[[0-9A-Za-z:.\-_~{SUB_DELIMS}]+\]|(?:[{unreserved}{SUB_DELIMS}]|{pct})*)(?::[0-9]*)?(?:/{ipchar}*)*|/?(?:{ipchar}+(?:/{ipchar}*)*)?)(?:\?(?:{ipchar}|[{IPRIVATE}/?])*)?(?:#(?:{ipchar}|[/?])*)?$"
+    ))
+    .unwrap()
+});
+/// components of an iri, rfc 3986 appendix B
+static COMPONENTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:([^:/?#]+):)?(?://([^/?#]*))?([^?#]*)(\?[^#]*)?(#.*)?$").unwrap()
+});
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum IriMode {
+    /// invalid iris are rejected
+    Strict,
+    /// invalid iris are rejected, valid ones are normalized
+    Normalize,
+    /// iris are kept as written
+    Lenient,
+}
+
+pub(crate) fn read_iri_mode<'a, C: Context<'a>>(
+    params: &Handle<JsObject>,
+    cx: &mut C,
+) -> Result<IriMode, TurtleDocError> {
+    let mode: Option<Handle<JsString>> =
+        params
+            .get_opt(cx, PARAMS_IRI_MODE)
+            .map_err(|e| TurtleDocError {
+                message: e.to_string(),
+            })?;
+    match mode.map(|m| m.value(cx)).as_deref() {
+        None | Some(IRI_MODE_LENIENT) => Ok(IriMode::Lenient),
+        Some(IRI_MODE_STRICT) => Ok(IriMode::Strict),
+        Some(IRI_MODE_NORMALIZE) => Ok(IriMode::Normalize),
+        Some(mode) => Err(TurtleDocError {
+            message: format!(
+                "unknown {PARAMS_IRI_MODE} '{mode}', expected {IRI_MODE_STRICT}, {IRI_MODE_NORMALIZE} or {IRI_MODE_LENIENT}"
+            ),
+        }),
+    }
+}
+
+/// why an iri is not a valid absolute iri (rfc 3987), None if it is valid
+pub(crate) fn iri_error(iri: &str) -> Option<String> {
+    if let Some(c) = IRI_CHARS.find(iri) {
+        return Some(format!("invalid character {:?}", c.as_str()));
+    }
+    if PERCENT_ENCODING.find_iter(iri).any(|m| m.len() != 3) {
+        return Some("invalid percent-encoding".into());
+    }
+    if !SCHEME.is_match(iri) {
+        return Some("not an absolute iri".into());
+    }
+    (!IRI.is_match(iri)).then(|| "invalid iri syntax".into())
+}
+
+/// syntax-based normalization of a valid iri (rfc 3987, section 5.3.2):
+/// scheme and host are lowercased, percent-encodings are uppercased or
+/// decoded when they encode an unreserved character, dot segments are removed
+pub(crate) fn normalize_iri(iri: &str) -> String {
+    let Some(components) = COMPONENTS.captures(iri) else {
+        return iri.to_string();
+    };
+    let mut normalized = String::with_capacity(iri.len());
+    if let Some(scheme) = components.get(1) {
+        normalized.push_str(&scheme.as_str().to_lowercase());
+        normalized.push(':');
+    }
+    if let Some(authority) = components.get(2) {
+        let authority = authority.as_str();
+        let (userinfo, host_port) = match authority.rsplit_once('@') {
+            Some((userinfo, host_port)) => (Some(userinfo), host_port),
+            None => (None, authority),
+        };
+        let (host, port) = match host_port.rfind(':') {
+            Some(idx) if !host_port[idx..].contains(']') => host_port.split_at(idx),
+            _ => (host_port, ""),
+        };
+        normalized.push_str("//");
+        if let Some(userinfo) = userinfo {
+            normalized.push_str(&normalize_percent_encoding(userinfo));
+            normalized.push('@');
+        }
+        normalized.push_str(&normalize_percent_encoding(&host.to_lowercase()));
+        normalized.push_str(port);
+    }
+    let path = normalize_percent_encoding(&components[3]);
+    normalized.push_str(&remove_dot_segments(&path));
+    for idx in [4, 5] {
+        if let Some(part) = components.get(idx) {
+            normalized.push_str(&normalize_percent_encoding(part.as_str()));
+        }
+    }
+    normalized
+}
+
+fn normalize_percent_encoding(value: &str) -> String {
+    PERCENT_ENCODING
+        .replace_all(value, |c: &regex::Captures| {
+            let encoded = &c[0];
+            match u8::from_str_radix(&encoded[1..], 16) {
+                Ok(b) if b.is_ascii_alphanumeric() || b"-._~".contains(&b) => {
+                    (b as char).to_string()
+                }
+                _ => encoded.to_uppercase(),
+            }
+        })
+        .into_owned()
+}
+
+/// rfc 3986, section 5.2.4
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output: Vec<&str> = vec![];
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let end = input
+                .char_indices()
+                .skip(1)
+                .find(|(_, c)| *c == '/')
+                .map(|(i, _)| i)
+                .unwrap_or(input.len());
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
+
+/// whether iri starts with a scheme
+pub(crate) fn is_absolute_iri(iri: &str) -> bool {
+    SCHEME.is_match(iri)
+}
+
+/// resolves a reference against an absolute base iri (rfc 3986, section 5.2.2)
+pub(crate) fn resolve_iri(reference: &str, base: &str) -> String {
+    let (Some(r), Some(b)) = (COMPONENTS.captures(reference), COMPONENTS.captures(base)) else {
+        return reference.to_string();
+    };
+    fn part<'h>(c: &regex::Captures<'h>, idx: usize) -> Option<&'h str> {
+        c.get(idx).map(|m| m.as_str())
+    }
+    let (scheme, authority, path, query) = if part(&r, 1).is_some() {
+        (
+            part(&r, 1),
+            part(&r, 2),
+            remove_dot_segments(&r[3]),
+            part(&r, 4),
+        )
+    } else if part(&r, 2).is_some() {
+        (
+            part(&b, 1),
+            part(&r, 2),
+            remove_dot_segments(&r[3]),
+            part(&r, 4),
+        )
+    } else if r[3].is_empty() {
+        (
+            part(&b, 1),
+            part(&b, 2),
+            b[3].to_string(),
+            part(&r, 4).or(part(&b, 4)),
+        )
+    } else if r[3].starts_with('/') {
+        (
+            part(&b, 1),
+            part(&b, 2),
+            remove_dot_segments(&r[3]),
+            part(&r, 4),
+        )
+    } else {
+        let merged = if part(&b, 2).is_some() && b[3].is_empty() {
+            format!("/{}", &r[3])
+        } else {
+            let directory = b[3].rfind('/').map(|i| &b[3][..=i]).unwrap_or("");
+            format!("{directory}{}", &r[3])
+        };
+        (
+            part(&b, 1),
+            part(&b, 2),
+            remove_dot_segments(&merged),
+            part(&r, 4),
+        )
+    };
+    let mut resolved = String::with_capacity(base.len() + reference.len());
+    if let Some(scheme) = scheme {
+        resolved.push_str(scheme);
+        resolved.push(':');
+    }
+    if let Some(authority) = authority {
+        resolved.push_str("//");
+        resolved.push_str(authority);
+    }
+    resolved.push_str(&path);
+    resolved.push_str(query.unwrap_or(""));
+    resolved.push_str(part(&r, 5).unwrap_or(""));
+    resolved
+}
+
+/// the shortest reference resolving to iri against base: empty or a
+/// fragment for the base document, otherwise a path below the directory of
+/// the base. iris outside of that directory are kept absolute.
+pub(crate) fn relativize_iri(iri: &str, base: &str) -> String {
+    let document = base.split('#').next().unwrap_or(base);
+    if let Some(rest) = iri.strip_prefix(document) {
+        if rest.is_empty() || rest.starts_with('#') {
+            return rest.to_string();
+        }
+    }
+    let document = document.split('?').next().unwrap_or(document);
+    let Some(directory) = document
+        .rfind('/')
+        .map(|i| &document[..=i])
+        .filter(|d| !d.ends_with("//"))
+    else {
+        return iri.to_string();
+    };
+    let Some(rest) = iri.strip_prefix(directory) else {
+        return iri.to_string();
+    };
+    let first_segment = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let has_dot_segments = rest
+        .split(['?', '#'])
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .any(|s| s == "." || s == "..");
+    if has_dot_segments {
+        iri.to_string()
+    } else if rest.is_empty() || first_segment.contains(':') || first_segment.is_empty() {
+        format!("./{rest}")
+    } else {
+        rest.to_string()
+    }
+}
+
+/// resolves the relative iris of every node, and the datatypes of literals,
+/// against base. returns false when there was nothing to resolve.
+pub(crate) fn resolve_relative_iris(triples: &mut [RdfJsonTriple], base: &str) -> bool {
+    fn resolve(node: &mut RdfJsonNodeResult, base: &str, resolved: &mut bool) {
+        match node {
+            RdfJsonNodeResult::SingleNode(node) => {
+                let iri = match node.typ.as_str() {
+                    "uri" => Some(&mut node.value),
+                    _ => node.datatype.as_mut(),
+                };
+                if let Some(iri) = iri.filter(|iri| !SCHEME.is_match(iri)) {
+                    *iri = resolve_iri(iri, base);
+                    *resolved = true;
+                }
+            }
+            RdfJsonNodeResult::ListNodes(nodes) => {
+                nodes.iter_mut().for_each(|n| resolve(n, base, resolved))
+            }
+        }
+    }
+    let mut resolved = false;
+    for triple in triples.iter_mut() {
+        for node in [
+            &mut triple.subject,
+            &mut triple.predicate,
+            &mut triple.object,
+        ] {
+            resolve(node, base, &mut resolved);
+        }
+    }
+    resolved
+}
+
+/// file url of a path, relative paths are made absolute
+pub(crate) fn file_iri(path: &str) -> Result<String, TurtleDocError> {
+    let path = std::path::absolute(path).map_err(|e| TurtleDocError {
+        message: format!("cannot resolve path {path}: {e}"),
+    })?;
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut iri = String::from("file://");
+    if !path.starts_with('/') {
+        iri.push('/');
+    }
+    for c in path.chars() {
+        if c.is_ascii_alphanumeric() || "-._~/!$&'()*+,;=:@".contains(c) || !c.is_ascii() {
+            iri.push(c);
+        } else {
+            iri.push_str(&format!("%{:02X}", c as u32));
+        }
+    }
+    Ok(iri)
+}
+
+/// validates, and normalizes depending on the mode, the iris of every node
+/// and the datatypes of literals
+pub(crate) fn apply_iri_mode(
+    triples: &mut [RdfJsonTriple],
+    mode: IriMode,
+) -> Result<(), TurtleDocError> {
+    fn apply(node: &mut RdfJsonNodeResult, mode: IriMode, errors: &mut Vec<(String, String)>) {
+        match node {
+            RdfJsonNodeResult::SingleNode(node) => apply_node(node, mode, errors),
+            RdfJsonNodeResult::ListNodes(nodes) => {
+                nodes.iter_mut().for_each(|n| apply(n, mode, errors))
+            }
+        }
+    }
+    fn apply_node(node: &mut RdfJsonNode, mode: IriMode, errors: &mut Vec<(String, String)>) {
+        let iri = match node.typ.as_str() {
+            "uri" => Some(&mut node.value),
+            _ => node.datatype.as_mut(),
+        };
+        let Some(iri) = iri else {
+            return;
+        };
+        match iri_error(iri) {
+            Some(e) => errors.push((iri.clone(), e)),
+            None if mode == IriMode::Normalize => *iri = normalize_iri(iri),
+            None => {}
+        }
+    }
+    if mode == IriMode::Lenient {
+        return Ok(());
+    }
+    let mut errors = vec![];
+    for (idx, triple) in triples.iter_mut().enumerate() {
+        let mut triple_errors = vec![];
+        for node in [
+            &mut triple.subject,
+            &mut triple.predicate,
+            &mut triple.object,
+        ] {
+            apply(node, mode, &mut triple_errors);
+        }
+        errors.extend(
+            triple_errors
+                .into_iter()
+                .map(|(iri, e)| format!("-statement {idx}: <{iri}>: {e}")),
+        );
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(TurtleDocError {
+            message: format!("invalid iris:\n{}", errors.join("\n")),
+        })
+    }
+}
+
+fn iris(triple: &Triple) -> impl Iterator<Item = &str> {
+    [&triple.subject, &triple.predicate, &triple.object]
+        .into_iter()
+        .filter_map(|term| match term {
+            Term::Iri(iri) => Some(iri.as_str()),
+            Term::Literal { datatype, .. } => datatype.as_deref(),
+            Term::BlankNode(_) => None,
+        })
+}
+
+pub fn check_iris(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut BTreeMap::new(),
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let array = JsArray::new(&mut cx, 0);
+    let mut count = 0;
+    for (position, triple) in triples.iter().enumerate() {
+        for (iri, message) in iris(triple).filter_map(|iri| iri_error(iri).map(|e| (iri, e))) {
+            let obj = cx.empty_object();
+            let position = cx.number(position as f64);
+            obj.set(&mut cx, "position", position)?;
+            let statement =
+                convert_rdf_json_triple_to_neon_object(&mut cx, RdfJsonTriple::from(triple))?;
+            obj.set(&mut cx, "statement", statement)?;
+            let iri = cx.string(iri);
+            obj.set(&mut cx, "iri", iri)?;
+            let message = cx.string(message);
+            obj.set(&mut cx, "message", message)?;
+            array.set(&mut cx, count, obj)?;
+            count += 1;
+        }
+    }
+    Ok(array.upcast())
+}
@@ -1,5 +1,21 @@
 use neon::prelude::*;
+mod aggregate;
+mod cbd;
+mod closure;
+mod compact;
+mod filter;
+mod graph;
+mod hash;
+mod iri;
+mod literals;
 mod obj;
+mod patch;
+mod rdfs;
+mod shacl;
+mod skolem;
+mod smush;
+mod sparql;
+mod stats;
 
 #[neon::main]
 fn main(mut cx: ModuleContext) -> NeonResult<()> {
@@ -7,5 +23,24 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("intersection", obj::intersection)?;
     cx.export_function("statements", obj::statements)?;
     cx.export_function("merge", obj::merge)?;
+    cx.export_function("symmetricDifference", obj::symmetric_difference)?;
+    cx.export_function("hash", hash::hash)?;
+    cx.export_function("diff", patch::diff)?;
+    cx.export_function("applyPatch", patch::apply_patch)?;
+    cx.export_function("skolemize", skolem::skolemize)?;
+    cx.export_function("deskolemize", skolem::deskolemize)?;
+    cx.export_function("sparql", sparql::sparql)?;
+    cx.export_function("sparqlUpdate", sparql::sparql_update)?;
+    cx.export_function("aggregate", aggregate::aggregate)?;
+    cx.export_function("stats", stats::stats)?;
+    cx.export_function("path", sparql::path)?;
+    cx.export_function("closure", closure::closure)?;
+    cx.export_function("cbd", cbd::cbd)?;
+    cx.export_function("infer", rdfs::infer)?;
+    cx.export_function("smush", smush::smush)?;
+    cx.export_function("reason", sparql::reason)?;
+    cx.export_function("validate", shacl::validate)?;
+    cx.export_function("checkLiterals", literals::check_literals)?;
+    cx.export_function("checkIris", iri::check_iris)?;
     Ok(())
 }
@@ -0,0 +1,587 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use neon::prelude::*;
+use regex::{Captures, Regex};
+use tortank::turtle::turtle_doc::{RdfJsonNode, RdfJsonNodeResult, RdfJsonTriple};
+
+use crate::{
+    graph::{Term, Triple, RDF_LANG_STRING},
+    obj::{convert_rdf_json_triple_to_neon_object, load_triples, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+static LANGUAGE_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z]{1,8}(-[a-zA-Z0-9]{1,8})*$").unwrap());
+static TIMEZONE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:Z|[+-](\d{2}):(\d{2}))$").unwrap());
+static DECIMAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[+-]?(\d+(\.\d*)?|\.\d+)$").unwrap());
+static INTEGER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[+-]?\d+$").unwrap());
+static DOUBLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([+-]?(\d+(\.\d*)?|\.\d+)([eE][+-]?\d+)?|[+-]?INF|NaN)$").unwrap()
+});
+static DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(-?\d{4,})-(\d{2})-(\d{2})$").unwrap());
+static TIME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2}):(\d{2}):(\d{2})(\.\d+)?$").unwrap());
+static G_YEAR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^-?\d{4,}$").unwrap());
+static G_YEAR_MONTH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(-?\d{4,})-(\d{2})$").unwrap());
+static G_MONTH: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^--(\d{2})$").unwrap());
+static G_DAY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^---(\d{2})$").unwrap());
+static G_MONTH_DAY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^--(\d{2})-(\d{2})$").unwrap());
+static DURATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^-?P(\d+Y)?(\d+M)?(\d+D)?(T(\d+H)?(\d+M)?(\d+(\.\d+)?S)?)?$").unwrap()
+});
+static HEX_BINARY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([0-9a-fA-F]{2})*$").unwrap());
+static BASE64_BINARY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9+/]*={0,2}$").unwrap());
+static NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\p{L}_:][\p{L}\p{N}_:.\-\u{B7}]*$").unwrap());
+static DATE_TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(-?\d{4,})-(\d{2})-(\d{2})T(\d{2}):(\d{2}):(\d{2})(\.\d+)?$").unwrap()
+});
+static DURATION_PARTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(-)?P(?:(\d+)Y)?(?:(\d+)M)?(?:(\d+)D)?(?:T(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)(\.\d+)?S)?)?$",
+    )
+    .unwrap()
+});
+static NMTOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\p{L}\p{N}_:.\-\u{B7}]+$").unwrap());
+
+/// bounds of the types derived from xsd:integer
+const INTEGER_RANGES: [(&str, i128, i128); 12] = [
+    ("nonPositiveInteger", i128::MIN, 0),
+    ("negativeInteger", i128::MIN, -1),
+    ("nonNegativeInteger", 0, i128::MAX),
+    ("positiveInteger", 1, i128::MAX),
+    ("long", i64::MIN as i128, i64::MAX as i128),
+    ("int", i32::MIN as i128, i32::MAX as i128),
+    ("short", i16::MIN as i128, i16::MAX as i128),
+    ("byte", i8::MIN as i128, i8::MAX as i128),
+    ("unsignedLong", 0, u64::MAX as i128),
+    ("unsignedInt", 0, u32::MAX as i128),
+    ("unsignedShort", 0, u16::MAX as i128),
+    ("unsignedByte", 0, u8::MAX as i128),
+];
+
+/// why the lexical form of a literal is not valid for its datatype, None if
+/// it is valid or the datatype is not a built-in xsd datatype
+pub(crate) fn lexical_error(literal: &Term) -> Option<String> {
+    let Term::Literal {
+        value,
+        datatype,
+        lang,
+    } = literal
+    else {
+        return None;
+    };
+    if let Some(lang) = lang {
+        return (!LANGUAGE_TAG.is_match(lang)).then(|| format!("invalid language tag {lang}"));
+    }
+    if datatype.as_deref() == Some(RDF_LANG_STRING) {
+        return Some("rdf:langString without a language tag".into());
+    }
+    let local = datatype.as_deref()?.strip_prefix(XSD)?;
+    let valid = match local {
+        "string" | "anyURI" => true,
+        "normalizedString" => !value.contains(['\r', '\n', '\t']),
+        "token" | "language" | "NMTOKEN" | "Name" | "NCName" | "ID" | "IDREF" | "ENTITY" => {
+            let token = !value.contains(['\r', '\n', '\t'])
+                && !value.starts_with(' ')
+                && !value.ends_with(' ')
+                && !value.contains("  ");
+            token
+                && match local {
+                    "language" => LANGUAGE_TAG.is_match(value),
+                    "NMTOKEN" => NMTOKEN.is_match(value),
+                    "Name" => NAME.is_match(value),
+                    "NCName" | "ID" | "IDREF" | "ENTITY" => {
+                        NAME.is_match(value) && !value.contains(':')
+                    }
+                    _ => true,
+                }
+        }
+        "boolean" => matches!(value.as_str(), "true" | "false" | "1" | "0"),
+        "decimal" => DECIMAL.is_match(value),
+        "integer" => INTEGER.is_match(value),
+        "float" | "double" => DOUBLE.is_match(value),
+        "date" => with_timezone(value, false, is_date),
+        "time" => with_timezone(value, false, is_time),
+        "dateTime" | "dateTimeStamp" => with_timezone(value, local == "dateTimeStamp", |v| {
+            v.split_once('T')
+                .map(|(date, time)| is_date(date) && is_time(time))
+                .unwrap_or(false)
+        }),
+        "gYear" => with_timezone(value, false, |v| G_YEAR.is_match(v)),
+        "gYearMonth" => with_timezone(value, false, |v| {
+            G_YEAR_MONTH
+                .captures(v)
+                .map(|c| is_month(&c[2]))
+                .unwrap_or(false)
+        }),
+        "gMonth" => with_timezone(value, false, |v| {
+            G_MONTH
+                .captures(v)
+                .map(|c| is_month(&c[1]))
+                .unwrap_or(false)
+        }),
+        "gDay" => with_timezone(value, false, |v| {
+            G_DAY
+                .captures(v)
+                .map(|c| (1..=31).contains(&number(&c[1])))
+                .unwrap_or(false)
+        }),
+        "gMonthDay" => with_timezone(value, false, |v| {
+            G_MONTH_DAY
+                .captures(v)
+                .map(|c| {
+                    is_month(&c[1])
+                        && (1..=days_in_month(2000, number(&c[1]))).contains(&number(&c[2]))
+                })
+                .unwrap_or(false)
+        }),
+        "duration" | "dayTimeDuration" | "yearMonthDuration" => {
+            let valid = DURATION.is_match(value) && !value.ends_with('P') && !value.ends_with('T');
+            let (date, time) = value.split_once('T').unwrap_or((value, ""));
+            valid
+                && match local {
+                    "dayTimeDuration" => !date.contains(['Y', 'M']),
+                    "yearMonthDuration" => !date.contains('D') && time.is_empty(),
+                    _ => true,
+                }
+        }
+        "hexBinary" => HEX_BINARY.is_match(value),
+        "base64Binary" => {
+            let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+            compact.len().is_multiple_of(4) && BASE64_BINARY.is_match(&compact)
+        }
+        _ => match INTEGER_RANGES.iter().find(|(name, _, _)| *name == local) {
+            Some((_, min, max)) => {
+                INTEGER.is_match(value)
+                    && value
+                        .trim_start_matches('+')
+                        .parse::<i128>()
+                        .map(|v| (*min..=*max).contains(&v))
+                        .unwrap_or(false)
+            }
+            None => return None,
+        },
+    };
+    (!valid).then(|| format!("invalid lexical form \"{value}\" for xsd:{local}"))
+}
+
+/// checks the optional (or required) timezone, then the rest of the value
+fn with_timezone(value: &str, required: bool, check: impl Fn(&str) -> bool) -> bool {
+    match TIMEZONE.captures(value) {
+        Some(tz) => {
+            let valid_tz = match (tz.get(1), tz.get(2)) {
+                (Some(hours), Some(minutes)) => {
+                    let (hours, minutes) = (number(hours.as_str()), number(minutes.as_str()));
+                    minutes <= 59 && (hours < 14 || (hours == 14 && minutes == 0))
+                }
+                _ => true,
+            };
+            let rest = &value[..tz.get(0).map(|m| m.start()).unwrap_or(value.len())];
+            valid_tz && check(rest)
+        }
+        None => !required && check(value),
+    }
+}
+
+fn is_date(value: &str) -> bool {
+    let Some(captures) = DATE.captures(value) else {
+        return false;
+    };
+    let year: i64 = captures[1].parse().unwrap_or(0);
+    let month = number(&captures[2]);
+    is_month(&captures[2]) && (1..=days_in_month(year, month)).contains(&number(&captures[3]))
+}
+
+/// 24:00:00 is allowed for the end of the day
+fn is_time(value: &str) -> bool {
+    let Some(captures) = TIME.captures(value) else {
+        return false;
+    };
+    let (hours, minutes, seconds) = (
+        number(&captures[1]),
+        number(&captures[2]),
+        number(&captures[3]),
+    );
+    let fraction_is_zero = captures
+        .get(4)
+        .map(|f| f.as_str().trim_start_matches('.').chars().all(|c| c == '0'))
+        .unwrap_or(true);
+    (hours < 24 && minutes < 60 && seconds < 60)
+        || (hours == 24 && minutes == 0 && seconds == 0 && fraction_is_zero)
+}
+
+fn is_month(value: &str) -> bool {
+    (1..=12).contains(&number(value))
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year.rem_euclid(4) == 0
+            && (year.rem_euclid(100) != 0 || year.rem_euclid(400) == 0) =>
+        {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn number(value: &str) -> u32 {
+    value.parse().unwrap_or(u32::MAX)
+}
+
+/// rewrites a literal node to the canonical lexical form of its datatype
+/// and lowercases its language tag. invalid literals are left as written.
+fn canonicalize_node(node: &mut RdfJsonNode) {
+    if node.typ != "literal" {
+        return;
+    }
+    if let Some(lang) = node.lang.as_mut() {
+        *lang = lang.to_lowercase();
+        return;
+    }
+    let Some(local) = node.datatype.as_deref().and_then(|dt| dt.strip_prefix(XSD)) else {
+        return;
+    };
+    let literal = Term::Literal {
+        value: node.value.clone(),
+        datatype: node.datatype.clone(),
+        lang: None,
+    };
+    if lexical_error(&literal).is_some() {
+        return;
+    }
+    if let Some(value) = canonical_value(&node.value, local) {
+        node.value = value;
+    }
+}
+
+pub(crate) fn canonicalize_triple(triple: &mut RdfJsonTriple) {
+    fn canonicalize(node: &mut RdfJsonNodeResult) {
+        match node {
+            RdfJsonNodeResult::SingleNode(node) => canonicalize_node(node),
+            RdfJsonNodeResult::ListNodes(nodes) => nodes.iter_mut().for_each(canonicalize),
+        }
+    }
+    canonicalize(&mut triple.object);
+}
+
+/// canonical form of a valid lexical form, None when the datatype has no
+/// other lexical forms for the same value
+fn canonical_value(value: &str, local: &str) -> Option<String> {
+    match local {
+        "boolean" => match value {
+            "1" => Some("true".into()),
+            "0" => Some("false".into()),
+            _ => None,
+        },
+        "integer" => Some(canonical_integer(value)),
+        "decimal" => Some(canonical_decimal(value)),
+        "float" => canonical_floating(value, value.parse::<f32>().ok().map(|f| format!("{f:E}"))),
+        "double" => canonical_floating(value, value.parse::<f64>().ok().map(|f| format!("{f:E}"))),
+        "dateTime" | "dateTimeStamp" => canonical_date_time(value),
+        "time" => canonical_time(value),
+        "date" | "gYear" | "gYearMonth" | "gMonth" | "gDay" | "gMonthDay" => {
+            canonical_timezone(value)
+        }
+        "duration" | "dayTimeDuration" | "yearMonthDuration" => canonical_duration(value, local),
+        "hexBinary" => Some(value.to_uppercase()),
+        "base64Binary" => Some(value.chars().filter(|c| !c.is_whitespace()).collect()),
+        _ if INTEGER_RANGES.iter().any(|(name, _, _)| *name == local) => {
+            Some(canonical_integer(value))
+        }
+        _ => None,
+    }
+}
+
+/// no sign for positive numbers, no leading zeros, arbitrary size
+fn canonical_integer(value: &str) -> String {
+    let (negative, digits) = split_sign(value);
+    let digits = digits.trim_start_matches('0');
+    match (negative, digits) {
+        (_, "") => "0".into(),
+        (true, digits) => format!("-{digits}"),
+        (false, digits) => digits.into(),
+    }
+}
+
+/// integral values are written without a decimal point, like tortank does
+fn canonical_decimal(value: &str) -> String {
+    let (negative, digits) = split_sign(value);
+    let (integral, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let integral = canonical_integer(integral);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if negative && (integral != "0" || !fraction.is_empty()) {
+        "-"
+    } else {
+        ""
+    };
+    if fraction.is_empty() {
+        format!("{sign}{integral}")
+    } else {
+        format!("{sign}{integral}.{fraction}")
+    }
+}
+
+/// scientific notation with a single non zero digit before the point, e.g 1.5E3
+fn canonical_floating(value: &str, formatted: Option<String>) -> Option<String> {
+    match value.trim_start_matches('+') {
+        "INF" => return Some("INF".into()),
+        "-INF" | "NaN" => return None,
+        _ => {}
+    }
+    let formatted = formatted?;
+    match formatted.as_str() {
+        // out of range values overflow, e.g 1e39 as a float
+        "inf" => return Some("INF".into()),
+        "-inf" => return Some("-INF".into()),
+        _ => {}
+    }
+    let (mantissa, exponent) = formatted.split_once('E')?;
+    if mantissa.contains('.') {
+        Some(formatted)
+    } else {
+        Some(format!("{mantissa}.0E{exponent}"))
+    }
+}
+
+/// minutes since 1970-01-01T00:00, shifted to UTC by the timezone offset
+fn epoch_minutes(captures: &Captures, offset: Option<i64>) -> Option<i64> {
+    let year: i64 = captures[1].parse().ok()?;
+    Some(
+        days_from_civil(year, number(&captures[2]), number(&captures[3])) * 1440
+            + i64::from(number(&captures[4])) * 60
+            + i64::from(number(&captures[5]))
+            - offset.unwrap_or(0),
+    )
+}
+
+/// instant of a dateTime as minutes since the epoch and seconds, in UTC when
+/// the value has a timezone, and whether it has one
+pub(crate) fn date_time_instant(value: &str) -> Option<(i64, f64, bool)> {
+    let (local, offset) = split_timezone(value)?;
+    let captures = DATE_TIME.captures(local)?;
+    let seconds = local[captures.get(6)?.start()..].parse().ok()?;
+    Some((epoch_minutes(&captures, offset)?, seconds, offset.is_some()))
+}
+
+/// instant of a date, the start of the day
+pub(crate) fn date_instant(value: &str) -> Option<(i64, f64, bool)> {
+    let (local, _) = split_timezone(value)?;
+    date_time_instant(&format!("{local}T00:00:00{}", &value[local.len()..]))
+}
+
+/// timezoned values are converted to UTC, 24:00:00 is the start of the next day
+fn canonical_date_time(value: &str) -> Option<String> {
+    let (local, offset) = split_timezone(value)?;
+    let captures = DATE_TIME.captures(local)?;
+    let minutes = epoch_minutes(&captures, offset)?;
+    let (year, month, day) = civil_from_days(minutes.div_euclid(1440));
+    let minutes = minutes.rem_euclid(1440);
+    Some(format!(
+        "{}-{month:02}-{day:02}T{:02}:{:02}:{}{}{}",
+        format_year(year),
+        minutes / 60,
+        minutes % 60,
+        &captures[6],
+        canonical_fraction(captures.get(7).map(|f| f.as_str())),
+        if offset.is_some() { "Z" } else { "" }
+    ))
+}
+
+fn canonical_time(value: &str) -> Option<String> {
+    let (local, offset) = split_timezone(value)?;
+    let captures = TIME.captures(local)?;
+    let minutes = (i64::from(number(&captures[1])) * 60 + i64::from(number(&captures[2]))
+        - offset.unwrap_or(0))
+    .rem_euclid(1440);
+    Some(format!(
+        "{:02}:{:02}:{}{}{}",
+        minutes / 60,
+        minutes % 60,
+        &captures[3],
+        canonical_fraction(captures.get(4).map(|f| f.as_str())),
+        if offset.is_some() { "Z" } else { "" }
+    ))
+}
+
+/// +00:00 and -00:00 are written Z
+fn canonical_timezone(value: &str) -> Option<String> {
+    let local = value
+        .strip_suffix("+00:00")
+        .or_else(|| value.strip_suffix("-00:00"))?;
+    Some(format!("{local}Z"))
+}
+
+/// months and seconds are the only components of a duration value, e.g
+/// PT36H is P1DT12H
+fn canonical_duration(value: &str, local: &str) -> Option<String> {
+    let captures = DURATION_PARTS.captures(value)?;
+    let part = |idx: usize| -> Option<u128> {
+        captures
+            .get(idx)
+            .map(|p| p.as_str().parse().ok())
+            .unwrap_or(Some(0))
+    };
+    let months = part(2)?.checked_mul(12)?.checked_add(part(3)?)?;
+    let seconds = part(4)?
+        .checked_mul(86400)?
+        .checked_add(part(5)?.checked_mul(3600)?)?
+        .checked_add(part(6)?.checked_mul(60)?)?
+        .checked_add(part(7)?)?;
+    let fraction = canonical_fraction(captures.get(8).map(|f| f.as_str()));
+    if months == 0 && seconds == 0 && fraction.is_empty() {
+        return Some(
+            if local == "yearMonthDuration" {
+                "P0M"
+            } else {
+                "PT0S"
+            }
+            .into(),
+        );
+    }
+    let mut canonical = String::from(if captures.get(1).is_some() { "-P" } else { "P" });
+    for (amount, unit) in [
+        (months / 12, 'Y'),
+        (months % 12, 'M'),
+        (seconds / 86400, 'D'),
+    ] {
+        if amount > 0 {
+            canonical.push_str(&format!("{amount}{unit}"));
+        }
+    }
+    let (hours, minutes, seconds) = (
+        (seconds % 86400) / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60,
+    );
+    if hours > 0 || minutes > 0 || seconds > 0 || !fraction.is_empty() {
+        canonical.push('T');
+        for (amount, unit) in [(hours, 'H'), (minutes, 'M')] {
+            if amount > 0 {
+                canonical.push_str(&format!("{amount}{unit}"));
+            }
+        }
+        if seconds > 0 || !fraction.is_empty() {
+            canonical.push_str(&format!("{seconds}{fraction}S"));
+        }
+    }
+    Some(canonical)
+}
+
+/// trailing zeros of fractional seconds are dropped, as is an empty fraction
+fn canonical_fraction(fraction: Option<&str>) -> String {
+    match fraction.map(|f| f.trim_end_matches('0')) {
+        Some(f) if f.len() > 1 => f.into(),
+        _ => String::new(),
+    }
+}
+
+fn split_sign(value: &str) -> (bool, &str) {
+    match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value.trim_start_matches('+')),
+    }
+}
+
+/// the value without its timezone, and the offset of the timezone in minutes
+fn split_timezone(value: &str) -> Option<(&str, Option<i64>)> {
+    let Some(tz) = TIMEZONE.captures(value) else {
+        return Some((value, None));
+    };
+    let start = tz.get(0)?.start();
+    let offset = match (tz.get(1), tz.get(2)) {
+        (Some(hours), Some(minutes)) => {
+            let offset =
+                i64::from(number(hours.as_str())) * 60 + i64::from(number(minutes.as_str()));
+            if value[start..].starts_with('-') {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => 0,
+    };
+    Some((&value[..start], Some(offset)))
+}
+
+fn format_year(year: i64) -> String {
+    if year < 0 {
+        format!("-{:04}", -year)
+    } else {
+        format!("{year:04}")
+    }
+}
+
+/// days since 1970-01-01 in the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// literals with an invalid lexical form or language tag, with the index of
+/// their statement in the model
+pub(crate) fn invalid_literals(triples: &[Triple]) -> Vec<(usize, &Triple, String)> {
+    triples
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, triple)| lexical_error(&triple.object).map(|e| (idx, triple, e)))
+        .collect()
+}
+
+pub fn check_literals(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut BTreeMap::new(),
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let invalid = invalid_literals(&triples);
+    let array = JsArray::new(&mut cx, invalid.len());
+    for (idx, (position, triple, message)) in invalid.into_iter().enumerate() {
+        let obj = cx.empty_object();
+        let position = cx.number(position as f64);
+        obj.set(&mut cx, "position", position)?;
+        let statement =
+            convert_rdf_json_triple_to_neon_object(&mut cx, RdfJsonTriple::from(triple))?;
+        obj.set(&mut cx, "statement", statement)?;
+        let message = cx.string(message);
+        obj.set(&mut cx, "message", message)?;
+        array.set(&mut cx, idx as u32, obj)?;
+    }
+    Ok(array.upcast())
+}
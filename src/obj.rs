@@ -1,37 +1,177 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use neon::prelude::*;
 use tortank::turtle::turtle_doc::{
     RdfJsonNode, RdfJsonNodeResult, RdfJsonTriple, Statement, TurtleDoc, TurtleDocError,
 };
 
-const PARAMS_LHS_PATH: &str = "lhsPath";
-const PARAMS_RHS_PATH: &str = "rhsPath";
-const PARAMS_LHS_DATA: &str = "lhsData";
-const PARAMS_RHS_DATA: &str = "rhsData";
-const PARAMS_SUBJECT_NODE: &str = "subject";
-const PARAMS_PREDICATE_NODE: &str = "predicate";
-const PARAMS_OBJECT_NODE: &str = "object";
-const PARAMS_OUTPUT_TYPE: &str = "outputType";
-const PARAMS_OUTPUT_FILE_PATH: &str = "outputFilePath";
+use crate::{
+    compact::{read_compactor, Compactor},
+    filter::StatementFilter,
+    graph::{standardize_apart, triples_from_rdf_json, Term, Triple},
+    iri::{
+        apply_iri_mode, file_iri, iri_error, read_iri_mode, relativize_iri, resolve_relative_iris,
+        IriMode,
+    },
+    literals::{canonicalize_triple, invalid_literals},
+    rdfs::load_entailed_triples,
+    sparql::prefix_declarations,
+};
+
+pub(crate) const PARAMS_LHS_PATH: &str = "lhsPath";
+pub(crate) const PARAMS_RHS_PATH: &str = "rhsPath";
+pub(crate) const PARAMS_LHS_DATA: &str = "lhsData";
+pub(crate) const PARAMS_RHS_DATA: &str = "rhsData";
+pub(crate) const PARAMS_OUTPUT_TYPE: &str = "outputType";
+pub(crate) const PARAMS_OUTPUT_FILE_PATH: &str = "outputFilePath";
 const PARAMS_BUF_SIZE: &str = "bufSize";
 const PARAMS_EXTRA_PREFIXES: &str = "extraPrefixes";
 const PARAMS_WELL_KNOWN_PREFIX: &str = "wellKnownPrefix";
-const PARAMS_MAPPER_FUNCTION: &str = "mapperFunction";
+pub(crate) const PARAMS_MAPPER_FUNCTION: &str = "mapperFunction";
+const PARAMS_INPUTS: &str = "inputs";
+const PARAMS_INPUT_PATH: &str = "path";
+const PARAMS_INPUT_DATA: &str = "data";
+const PARAMS_BLANK_NODE_MERGE: &str = "blankNodeMerge";
+const PARAMS_VALIDATE_LITERALS: &str = "validateLiterals";
+const PARAMS_CANONICALIZE_LITERALS: &str = "canonicalizeLiterals";
+const PARAMS_BASE_IRI: &str = "baseIri";
+const PARAMS_LHS_BASE_IRI: &str = "lhsBaseIri";
+const PARAMS_RHS_BASE_IRI: &str = "rhsBaseIri";
+const PARAMS_RELATIVIZE_OUTPUT: &str = "relativizeOutput";
+
+const BLANK_NODE_MERGE_RENAME: &str = "rename";
+const BLANK_NODE_MERGE_UNION: &str = "union";
 
 pub enum DocType<'a> {
     TurtleDoc(TurtleDoc<'a>),
     RdfJsonTriple((Vec<RdfJsonTriple>, BTreeMap<String, String>)),
 }
 
+enum SetOperation {
+    Union,
+    Difference,
+    Intersection,
+    SymmetricDifference,
+}
+
+/// applies the set operation over all the models, from left to right
+fn set_operation(op: SetOperation, models: Vec<Vec<Triple>>) -> Vec<Triple> {
+    let mut models = models.into_iter();
+    let first = models.next().unwrap_or_default();
+    match op {
+        SetOperation::Union => {
+            let mut seen: HashSet<Triple> = first.iter().cloned().collect();
+            let mut union = first;
+            for triple in models.flatten() {
+                if seen.insert(triple.clone()) {
+                    union.push(triple);
+                }
+            }
+            union
+        }
+        SetOperation::Difference => {
+            let others: HashSet<Triple> = models.flatten().collect();
+            first.into_iter().filter(|t| !others.contains(t)).collect()
+        }
+        SetOperation::Intersection => models.fold(first, |acc, model| {
+            let model: HashSet<Triple> = model.into_iter().collect();
+            acc.into_iter().filter(|t| model.contains(t)).collect()
+        }),
+        SetOperation::SymmetricDifference => models.fold(first, |acc, model| {
+            let lhs: HashSet<&Triple> = acc.iter().collect();
+            let rhs: HashSet<&Triple> = model.iter().collect();
+            let mut res: Vec<Triple> = acc.iter().filter(|t| !rhs.contains(t)).cloned().collect();
+            res.extend(model.iter().filter(|t| !lhs.contains(t)).cloned());
+            res
+        }),
+    }
+}
+
+/// n-ary set operations, when the models are provided with inputs
+fn inputs_set_operation<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    op: SetOperation,
+) -> NeonResult<Option<Handle<'a, JsValue>>> {
+    let mut prefixes = BTreeMap::new();
+    match load_inputs(params, cx, &mut prefixes) {
+        Ok(Some(models)) => {
+            let triples = set_operation(op, models);
+            make_triples_response(params, cx, &triples, &prefixes).map(Some)
+        }
+        Ok(None) => Ok(None),
+        Err(e) => cx.throw_error(e.message),
+    }
+}
+
+/// models provided with inputs, or lhs and rhs
+fn load_operands<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    prefixes: &mut BTreeMap<String, String>,
+) -> Result<Vec<Vec<Triple>>, TurtleDocError> {
+    if let Some(models) = load_inputs(params, cx, prefixes)? {
+        return Ok(models);
+    }
+    let lhs = load_triples(params, cx, prefixes, PARAMS_LHS_PATH, PARAMS_LHS_DATA);
+    let rhs = load_triples(params, cx, prefixes, PARAMS_RHS_PATH, PARAMS_RHS_DATA);
+    match (lhs, rhs) {
+        (Ok(lhs), Ok(rhs)) => Ok(vec![lhs, rhs]),
+        (Ok(_), Err(e)) | (Err(e), Ok(_)) => Err(e),
+        (Err(e1), Err(e2)) => Err(TurtleDocError {
+            message: format!("error:\n-{}\n-{}", e1.message, e2.message),
+        }),
+    }
+}
+
+pub fn symmetric_difference(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let mut prefixes = BTreeMap::new();
+    match load_operands(&params, &mut cx, &mut prefixes) {
+        Ok(models) => {
+            let triples = set_operation(SetOperation::SymmetricDifference, models);
+            make_triples_response(&params, &mut cx, &triples, &prefixes)
+        }
+        Err(e) => cx.throw_error(e.message),
+    }
+}
+
 pub fn merge(mut cx: FunctionContext) -> JsResult<JsValue> {
     let params = cx.argument::<JsObject>(0)?;
+    let blank_node_merge: Option<String> = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_BLANK_NODE_MERGE)?
+        .map(|m| m.value(&mut cx));
+    match blank_node_merge.as_deref() {
+        Some(BLANK_NODE_MERGE_UNION) => {}
+        None | Some(BLANK_NODE_MERGE_RENAME) => {
+            // standard rdf merge: blank nodes of different inputs are never the same node
+            let mut prefixes = BTreeMap::new();
+            return match load_operands(&params, &mut cx, &mut prefixes) {
+                Ok(models) => {
+                    let triples = set_operation(SetOperation::Union, standardize_apart(models));
+                    make_triples_response(&params, &mut cx, &triples, &prefixes)
+                }
+                Err(e) => cx.throw_error(e.message),
+            };
+        }
+        Some(m) => return cx.throw_error(format!("unknown {PARAMS_BLANK_NODE_MERGE} {m}")),
+    }
+    if let Some(res) = inputs_set_operation(&params, &mut cx, SetOperation::Union)? {
+        return Ok(res);
+    }
     let mut buf_lhs = String::new();
     let mut buf_rhs = String::new();
+    let mut prefixes = BTreeMap::new();
     let ttl_doc_lhs = make_doc(
         &params,
         &mut cx,
         &mut buf_lhs,
+        &mut prefixes,
         PARAMS_LHS_PATH,
         PARAMS_LHS_DATA,
     );
@@ -39,23 +179,24 @@ pub fn merge(mut cx: FunctionContext) -> JsResult<JsValue> {
         &params,
         &mut cx,
         &mut buf_rhs,
+        &mut prefixes,
         PARAMS_RHS_PATH,
         PARAMS_RHS_DATA,
     );
 
     match (ttl_doc_lhs, ttl_doc_rhs) {
         (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::TurtleDoc(rhs))) => {
-            make_response(&params, &mut cx, lhs + rhs)
+            make_response(&params, &mut cx, lhs + rhs, &prefixes)
         }
-        (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::RdfJsonTriple((rhs, prefixes)))) => {
-            match rdf_json_triple_to_doc(&rhs[..], prefixes) {
-                Ok(doc) => make_response(&params, &mut cx, lhs + doc),
+        (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::RdfJsonTriple((rhs, rhs_prefixes)))) => {
+            match rdf_json_triple_to_doc(&rhs[..], rhs_prefixes) {
+                Ok(doc) => make_response(&params, &mut cx, lhs + doc, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
-        (Ok(DocType::RdfJsonTriple((lhs, prefixes))), Ok(DocType::TurtleDoc(rhs))) => {
-            match rdf_json_triple_to_doc(&lhs[..], prefixes) {
-                Ok(doc) => make_response(&params, &mut cx, doc + rhs),
+        (Ok(DocType::RdfJsonTriple((lhs, lhs_prefixes))), Ok(DocType::TurtleDoc(rhs))) => {
+            match rdf_json_triple_to_doc(&lhs[..], lhs_prefixes) {
+                Ok(doc) => make_response(&params, &mut cx, doc + rhs, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
@@ -67,7 +208,7 @@ pub fn merge(mut cx: FunctionContext) -> JsResult<JsValue> {
                 rdf_json_triple_to_doc(&lhs[..], lhs_prefixes),
                 rdf_json_triple_to_doc(&rhs[..], rhs_prefixes),
             ) {
-                (Ok(lhs), Ok(rhs)) => make_response(&params, &mut cx, lhs + rhs),
+                (Ok(lhs), Ok(rhs)) => make_response(&params, &mut cx, lhs + rhs, &prefixes),
                 (Ok(_), Err(e)) | (Err(e), Ok(_)) => cx.throw_error(e.message),
                 (Err(e1), Err(e2)) => {
                     cx.throw_error(format!("error:\n-{}\n-{}", e1.message, e2.message))
@@ -80,13 +221,18 @@ pub fn merge(mut cx: FunctionContext) -> JsResult<JsValue> {
 }
 pub fn difference(mut cx: FunctionContext) -> JsResult<JsValue> {
     let params = cx.argument::<JsObject>(0)?;
+    if let Some(res) = inputs_set_operation(&params, &mut cx, SetOperation::Difference)? {
+        return Ok(res);
+    }
 
     let mut buf_lhs = String::new();
     let mut buf_rhs = String::new();
+    let mut prefixes = BTreeMap::new();
     let ttl_doc_lhs = make_doc(
         &params,
         &mut cx,
         &mut buf_lhs,
+        &mut prefixes,
         PARAMS_LHS_PATH,
         PARAMS_LHS_DATA,
     );
@@ -94,6 +240,7 @@ pub fn difference(mut cx: FunctionContext) -> JsResult<JsValue> {
         &params,
         &mut cx,
         &mut buf_rhs,
+        &mut prefixes,
         PARAMS_RHS_PATH,
         PARAMS_RHS_DATA,
     );
@@ -102,27 +249,28 @@ pub fn difference(mut cx: FunctionContext) -> JsResult<JsValue> {
         cx: &mut C,
         lhs: TurtleDoc<'b>,
         rhs: TurtleDoc<'b>,
+        prefixes: &BTreeMap<String, String>,
     ) -> JsResult<'a, JsValue> {
         let diff = lhs.difference(&rhs);
         match diff {
-            Ok(model) => make_response(params, cx, model),
+            Ok(model) => make_response(params, cx, model, prefixes),
 
             Err(e) => cx.throw_error(e.to_string()),
         }
     }
     match (ttl_doc_lhs, ttl_doc_rhs) {
         (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::TurtleDoc(rhs))) => {
-            diff_fn(&params, &mut cx, lhs, rhs)
+            diff_fn(&params, &mut cx, lhs, rhs, &prefixes)
         }
-        (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::RdfJsonTriple((rhs, prefixes)))) => {
-            match rdf_json_triple_to_doc(&rhs[..], prefixes) {
-                Ok(doc) => diff_fn(&params, &mut cx, lhs, doc),
+        (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::RdfJsonTriple((rhs, rhs_prefixes)))) => {
+            match rdf_json_triple_to_doc(&rhs[..], rhs_prefixes) {
+                Ok(doc) => diff_fn(&params, &mut cx, lhs, doc, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
         (Ok(DocType::RdfJsonTriple((lhs, lhs_prefixes))), Ok(DocType::TurtleDoc(rhs))) => {
             match rdf_json_triple_to_doc(&lhs[..], lhs_prefixes) {
-                Ok(doc) => diff_fn(&params, &mut cx, doc, rhs),
+                Ok(doc) => diff_fn(&params, &mut cx, doc, rhs, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
@@ -134,7 +282,7 @@ pub fn difference(mut cx: FunctionContext) -> JsResult<JsValue> {
                 rdf_json_triple_to_doc(&lhs[..], lhs_prefixes),
                 rdf_json_triple_to_doc(&rhs[..], rhs_prefixes),
             ) {
-                (Ok(lhs), Ok(rhs)) => diff_fn(&params, &mut cx, lhs, rhs),
+                (Ok(lhs), Ok(rhs)) => diff_fn(&params, &mut cx, lhs, rhs, &prefixes),
                 (Ok(_), Err(e)) | (Err(e), Ok(_)) => cx.throw_error(e.message),
                 (Err(e1), Err(e2)) => {
                     cx.throw_error(format!("error:\n-{}\n-{}", e1.message, e2.message))
@@ -150,57 +298,48 @@ fn filter_statements<'a, 'b, C: Context<'a>>(
     params: &'b Handle<'b, JsObject>,
     cx: &mut C,
     ttl_doc: &'b TurtleDoc<'b>,
+    prefixes: &BTreeMap<String, String>,
 ) -> Result<TurtleDoc<'b>, TurtleDocError> {
-    let subject: Option<String> = params
-        .get_opt::<JsString, _, _>(cx, PARAMS_SUBJECT_NODE)
-        .map(|s| {
-            if let Some(s) = s {
-                Some(s.value(cx))
-            } else {
-                None
-            }
-        })
-        .map_err(|e| TurtleDocError {
-            message: e.to_string(),
-        })?;
-
-    let predicate: Option<String> = params
-        .get_opt::<JsString, _, _>(cx, PARAMS_PREDICATE_NODE)
-        .map(|s| {
-            if let Some(s) = s {
-                Some(s.value(cx))
-            } else {
-                None
-            }
-        })
-        .map_err(|e| TurtleDocError {
-            message: e.to_string(),
-        })?;
-
-    let object: Option<String> = params
-        .get_opt::<JsString, _, _>(cx, PARAMS_OBJECT_NODE)
-        .map(|s| {
-            if let Some(s) = s {
-                Some(s.value(cx))
-            } else {
-                None
-            }
-        })
-        .map_err(|e| TurtleDocError {
-            message: e.to_string(),
-        })?;
-
-    let stmts: Vec<&Statement> = ttl_doc.parse_and_list_statements(subject, predicate, object)?;
-
+    let filter = StatementFilter::read(params, cx, prefixes)?;
+    let (subject, predicate, object) = filter.exact();
+    let mut stmts: Vec<&Statement> =
+        ttl_doc.parse_and_list_statements(subject, predicate, object)?;
+    if !filter.is_exact() {
+        stmts.retain(|stmt| filter.matches(&(*stmt).into()));
+    }
     TurtleDoc::try_from(stmts)
 }
 
 pub fn statements(mut cx: FunctionContext) -> JsResult<JsValue> {
     let params = cx.argument::<JsObject>(0)?;
 
+    let mut prefixes = BTreeMap::new();
+    match load_entailed_triples(&params, &mut cx, &mut prefixes) {
+        Ok(Some(triples)) => {
+            let json_triples: Vec<RdfJsonTriple> = triples.iter().map(Into::into).collect();
+            let doc = match rdf_json_triple_to_doc(&json_triples[..], prefixes.clone()) {
+                Ok(doc) => doc,
+                Err(e) => return cx.throw_error(e.message),
+            };
+            return match filter_statements(&params, &mut cx, &doc, &prefixes) {
+                Ok(doc) => make_response(&params, &mut cx, doc, &prefixes),
+                Err(e) => cx.throw_error(e.message),
+            };
+        }
+        Ok(None) => {}
+        Err(e) => return cx.throw_error(e.message),
+    }
+
     let mut buf = String::new();
 
-    let ttl_doc = make_doc(&params, &mut cx, &mut buf, PARAMS_LHS_PATH, PARAMS_LHS_DATA);
+    let ttl_doc = make_doc(
+        &params,
+        &mut cx,
+        &mut buf,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    );
 
     match ttl_doc {
         Ok(ttl_doc) => {
@@ -213,8 +352,8 @@ pub fn statements(mut cx: FunctionContext) -> JsResult<JsValue> {
                     }
                 }
             }?;
-            match filter_statements(&params, &mut cx, &doc) {
-                Ok(doc) => make_response(&params, &mut cx, doc),
+            match filter_statements(&params, &mut cx, &doc, &prefixes) {
+                Ok(doc) => make_response(&params, &mut cx, doc, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
@@ -223,13 +362,18 @@ pub fn statements(mut cx: FunctionContext) -> JsResult<JsValue> {
 }
 pub fn intersection(mut cx: FunctionContext) -> JsResult<JsValue> {
     let params = cx.argument::<JsObject>(0)?;
+    if let Some(res) = inputs_set_operation(&params, &mut cx, SetOperation::Intersection)? {
+        return Ok(res);
+    }
 
     let mut buf_lhs = String::new();
     let mut buf_rhs = String::new();
+    let mut prefixes = BTreeMap::new();
     let ttl_doc_lhs = make_doc(
         &params,
         &mut cx,
         &mut buf_lhs,
+        &mut prefixes,
         PARAMS_LHS_PATH,
         PARAMS_LHS_DATA,
     );
@@ -237,6 +381,7 @@ pub fn intersection(mut cx: FunctionContext) -> JsResult<JsValue> {
         &params,
         &mut cx,
         &mut buf_rhs,
+        &mut prefixes,
         PARAMS_RHS_PATH,
         PARAMS_RHS_DATA,
     );
@@ -245,27 +390,28 @@ pub fn intersection(mut cx: FunctionContext) -> JsResult<JsValue> {
         cx: &mut C,
         lhs: TurtleDoc<'b>,
         rhs: TurtleDoc<'b>,
+        prefixes: &BTreeMap<String, String>,
     ) -> JsResult<'a, JsValue> {
         let diff = lhs.intersection(&rhs);
         match diff {
-            Ok(model) => make_response(params, cx, model),
+            Ok(model) => make_response(params, cx, model, prefixes),
 
             Err(e) => cx.throw_error(e.to_string()),
         }
     }
     match (ttl_doc_lhs, ttl_doc_rhs) {
         (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::TurtleDoc(rhs))) => {
-            intersection_fn(&params, &mut cx, lhs, rhs)
+            intersection_fn(&params, &mut cx, lhs, rhs, &prefixes)
         }
-        (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::RdfJsonTriple((rhs, prefixes)))) => {
-            match rdf_json_triple_to_doc(&rhs[..], prefixes) {
-                Ok(doc) => intersection_fn(&params, &mut cx, lhs, doc),
+        (Ok(DocType::TurtleDoc(lhs)), Ok(DocType::RdfJsonTriple((rhs, rhs_prefixes)))) => {
+            match rdf_json_triple_to_doc(&rhs[..], rhs_prefixes) {
+                Ok(doc) => intersection_fn(&params, &mut cx, lhs, doc, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
-        (Ok(DocType::RdfJsonTriple((lhs, prefixes))), Ok(DocType::TurtleDoc(rhs))) => {
-            match rdf_json_triple_to_doc(&lhs[..], prefixes) {
-                Ok(doc) => intersection_fn(&params, &mut cx, doc, rhs),
+        (Ok(DocType::RdfJsonTriple((lhs, lhs_prefixes))), Ok(DocType::TurtleDoc(rhs))) => {
+            match rdf_json_triple_to_doc(&lhs[..], lhs_prefixes) {
+                Ok(doc) => intersection_fn(&params, &mut cx, doc, rhs, &prefixes),
                 Err(e) => cx.throw_error(e.message),
             }
         }
@@ -277,7 +423,7 @@ pub fn intersection(mut cx: FunctionContext) -> JsResult<JsValue> {
                 rdf_json_triple_to_doc(&lhs[..], lhs_prefixes),
                 rdf_json_triple_to_doc(&rhs[..], rhs_prefixes),
             ) {
-                (Ok(lhs), Ok(rhs)) => intersection_fn(&params, &mut cx, lhs, rhs),
+                (Ok(lhs), Ok(rhs)) => intersection_fn(&params, &mut cx, lhs, rhs, &prefixes),
                 (Ok(_), Err(e)) | (Err(e), Ok(_)) => cx.throw_error(e.message),
                 (Err(e1), Err(e2)) => {
                     cx.throw_error(format!("error:\n-{}\n-{}", e1.message, e2.message))
@@ -299,7 +445,7 @@ fn rdf_json_triple_to_doc(
     Ok(doc)
 }
 
-fn convert_rdf_json_triple_to_neon_object<'a, C: Context<'a>>(
+pub(crate) fn convert_rdf_json_triple_to_neon_object<'a, C: Context<'a>>(
     cx: &mut C,
     triple: RdfJsonTriple,
 ) -> JsResult<'a, JsObject> {
@@ -317,7 +463,7 @@ fn convert_rdf_json_triple_to_neon_object<'a, C: Context<'a>>(
     stmt_obj.set(cx, "object", object)?;
     Ok(stmt_obj)
 }
-fn convert_rdf_json_node_result_to_neon_object<'a, C: Context<'a>>(
+pub(crate) fn convert_rdf_json_node_result_to_neon_object<'a, C: Context<'a>>(
     cx: &mut C,
     node_res: RdfJsonNodeResult,
 ) -> JsResult<'a, JsObject> {
@@ -351,7 +497,7 @@ fn convert_rdf_json_node_result_to_neon_object<'a, C: Context<'a>>(
     }
 }
 
-fn convert_neon_object_to_rdf_js_triple<'a, C: Context<'a>>(
+pub(crate) fn convert_neon_object_to_rdf_js_triple<'a, C: Context<'a>>(
     cx: &mut C,
     obj: Handle<JsObject>,
 ) -> Result<RdfJsonTriple, TurtleDocError> {
@@ -426,6 +572,7 @@ fn make_response<'a, 'b, C: Context<'a>>(
     params: &'b Handle<'b, JsObject>,
     cx: &mut C,
     doc: TurtleDoc<'b>,
+    prefixes: &BTreeMap<String, String>,
 ) -> JsResult<'a, JsValue> {
     // todo refactor this to offer more output type
     let as_n3: bool = params
@@ -439,55 +586,204 @@ fn make_response<'a, 'b, C: Context<'a>>(
         .map(|buf| buf.map(|b| b.value(cx).abs() as usize))?;
 
     let js_mapper_func = params.get_opt::<JsFunction, _, _>(cx, PARAMS_MAPPER_FUNCTION)?;
+    let compactor = match read_compactor(params, cx, prefixes) {
+        Ok(compactor) => compactor,
+        Err(e) => return cx.throw_error(e.message),
+    };
 
-    if let Some(opf) = output_file_path {
-        return match doc.to_file(opf, buf_size, !as_n3) {
+    let relativize_output = params
+        .get_opt::<JsBoolean, _, _>(cx, PARAMS_RELATIVIZE_OUTPUT)?
+        .map(|b| b.value(cx))
+        .unwrap_or(false);
+    let base = if as_n3 && relativize_output {
+        match read_base_iri(params, params, cx, PARAMS_LHS_PATH) {
+            Ok(base) => base,
+            Err(e) => return cx.throw_error(e.message),
+        }
+    } else {
+        None
+    };
+
+    if as_n3 && (compactor.is_some() || base.is_some()) {
+        // tortank does not write prefixed names nor relative iris
+        let ttl = match (compactor, base) {
+            (Some(compactor), base) => {
+                doc_to_triples(&doc).map(|triples| compactor.to_n3(&triples, base.as_deref()))
+            }
+            (None, Some(base)) => relativized_n3(&doc, &base, prefixes),
+            (None, None) => Ok(doc.to_string()),
+        };
+        let ttl = match ttl {
+            Ok(ttl) => ttl,
+            Err(e) => return cx.throw_error(e.message),
+        };
+        if let Some(opf) = output_file_path {
+            return match std::fs::write(opf, ttl) {
+                Ok(_) => Ok(cx.boolean(true).upcast()),
+                Err(e) => cx.throw_error(e.to_string()),
+            };
+        }
+        let s = cx.string(ttl);
+        let s = s.as_value(cx);
+        Ok(s)
+    } else if let Some(opf) = output_file_path {
+        // chunks of size 0 would panic on an empty document
+        let buf_size = buf_size.filter(|b| *b > 0).or(Some(doc.len().max(1)));
+        match doc.to_file(opf, buf_size, !as_n3) {
             Ok(_) => {
                 let b = cx.boolean(true);
                 let b = b.as_value(cx);
                 Ok(b)
             }
             Err(e) => cx.throw_error(e.to_string()),
-        };
+        }
     } else if as_n3 {
-        let ttl = doc.to_string();
-        let s = cx.string(ttl);
+        let s = cx.string(doc.to_string());
         let s = s.as_value(cx);
         Ok(s)
     } else {
         let json_stmts: Vec<RdfJsonTriple> = (&doc).into();
-        let array = JsArray::new(cx, json_stmts.len());
-        let mut count = 0 as u32;
-        for triple in json_stmts.into_iter() {
-            let stmt_obj = convert_rdf_json_triple_to_neon_object(cx, triple)?;
-            if let Some(fun) = js_mapper_func {
-                let mapped_obj: Handle<JsValue> = fun.call_with(cx).arg(stmt_obj).apply(cx)?;
-                if !mapped_obj.is_a::<JsNull, _>(cx) && !mapped_obj.is_a::<JsUndefined, _>(cx) {
-                    array.set(cx, count, mapped_obj)?;
-                    count += 1;
-                }
-            } else {
-                array.set(cx, count, stmt_obj)?;
+        let array = make_js_array(cx, json_stmts, js_mapper_func, compactor.as_ref())?;
+        Ok(array.upcast())
+    }
+}
+
+pub(crate) fn make_js_array<'a, C: Context<'a>>(
+    cx: &mut C,
+    json_stmts: Vec<RdfJsonTriple>,
+    js_mapper_func: Option<Handle<JsFunction>>,
+    compactor: Option<&Compactor>,
+) -> JsResult<'a, JsArray> {
+    let array = JsArray::new(cx, json_stmts.len());
+    let mut count = 0_u32;
+    for triple in json_stmts.into_iter() {
+        let nodes = compactor.map(|_| {
+            [
+                ("subject", triple.subject.clone()),
+                ("predicate", triple.predicate.clone()),
+                ("object", triple.object.clone()),
+            ]
+        });
+        let stmt_obj = convert_rdf_json_triple_to_neon_object(cx, triple)?;
+        if let (Some(compactor), Some(nodes)) = (compactor, nodes) {
+            for (key, node) in nodes {
+                let node_obj: Handle<JsObject> = stmt_obj.get(cx, key)?;
+                compactor.compact_js_node(cx, node_obj, &node)?;
+            }
+        }
+        if let Some(fun) = js_mapper_func {
+            let mapped_obj: Handle<JsValue> = fun.call_with(cx).arg(stmt_obj).apply(cx)?;
+            if !mapped_obj.is_a::<JsNull, _>(cx) && !mapped_obj.is_a::<JsUndefined, _>(cx) {
+                array.set(cx, count, mapped_obj)?;
                 count += 1;
             }
+        } else {
+            array.set(cx, count, stmt_obj)?;
+            count += 1;
         }
-        return Ok(array.upcast());
     }
+    Ok(array)
 }
 
 fn make_doc<'a, 'b>(
     params: &'b Handle<'b, JsObject>,
     cx: &'b mut FunctionContext,
     buf: &'a mut String,
+    prefixes: &mut BTreeMap<String, String>,
     key_path: &'static str,
     key_data: &'static str,
 ) -> Result<DocType<'a>, TurtleDocError> {
+    make_doc_from(params, params, cx, buf, prefixes, key_path, key_data)
+}
+
+/// same as make_doc, but path and data are read from source, e.g an element of inputs
+fn make_doc_from<'a, 'b>(
+    params: &'b Handle<'b, JsObject>,
+    source: &'b Handle<'b, JsObject>,
+    cx: &'b mut FunctionContext,
+    buf: &'a mut String,
+    prefixes: &mut BTreeMap<String, String>,
+    key_path: &'static str,
+    key_data: &'static str,
+) -> Result<DocType<'a>, TurtleDocError> {
+    let validate_literals = params
+        .get_opt::<JsBoolean, _, _>(cx, PARAMS_VALIDATE_LITERALS)
+        .map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?
+        .map(|b| b.value(cx))
+        .unwrap_or(false);
+    let canonicalize_literals = params
+        .get_opt::<JsBoolean, _, _>(cx, PARAMS_CANONICALIZE_LITERALS)
+        .map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?
+        .map(|b| b.value(cx))
+        .unwrap_or(false);
+    let iri_mode = read_iri_mode(params, cx)?;
+    let base_iri = read_base_iri(params, source, cx, key_path)?;
+    let (mut doc_type, doc_prefixes) = read_doc_from(params, source, cx, buf, key_path, key_data)?;
+    for (prefix, namespace) in doc_prefixes.iter() {
+        // the prefixes of an earlier model, e.g lhs, win
+        prefixes
+            .entry(prefix.clone())
+            .or_insert_with(|| namespace.clone());
+    }
+    if let (Some(base), DocType::TurtleDoc(doc)) = (&base_iri, &doc_type) {
+        // most documents have no relative iri, keep the parsed doc then
+        let mut triples: Vec<RdfJsonTriple> = doc.into();
+        if resolve_relative_iris(&mut triples, base) {
+            doc_type = DocType::RdfJsonTriple((triples, doc_prefixes.clone()));
+        }
+    } else if let (Some(base), DocType::RdfJsonTriple((triples, _))) = (&base_iri, &mut doc_type) {
+        resolve_relative_iris(triples, base);
+    }
+    if canonicalize_literals || iri_mode != IriMode::Lenient {
+        // before validation, so js literals tortank would reject (e.g "1"
+        // for a boolean) are rewritten first
+        let (mut triples, prefixes) = match doc_type {
+            DocType::TurtleDoc(doc) => ((&doc).into(), doc_prefixes),
+            DocType::RdfJsonTriple(rjs) => rjs,
+        };
+        apply_iri_mode(&mut triples, iri_mode)?;
+        if canonicalize_literals {
+            triples.iter_mut().for_each(canonicalize_triple);
+        }
+        doc_type = DocType::RdfJsonTriple((triples, prefixes));
+    }
+    if validate_literals {
+        let triples = match &doc_type {
+            DocType::TurtleDoc(doc) => doc_to_triples(doc)?,
+            DocType::RdfJsonTriple((rjs, _)) => rdf_json_to_triples(&rjs[..])?,
+        };
+        let invalid = invalid_literals(&triples);
+        if !invalid.is_empty() {
+            let errors: Vec<String> = invalid
+                .into_iter()
+                .map(|(idx, triple, message)| format!("-statement {idx}: {triple}: {message}"))
+                .collect();
+            return Err(TurtleDocError {
+                message: format!("invalid literals:\n{}", errors.join("\n")),
+            });
+        }
+    }
+    Ok(doc_type)
+}
+
+fn read_doc_from<'a, 'b>(
+    params: &'b Handle<'b, JsObject>,
+    source: &'b Handle<'b, JsObject>,
+    cx: &'b mut FunctionContext,
+    buf: &'a mut String,
+    key_path: &'static str,
+    key_data: &'static str,
+) -> Result<(DocType<'a>, BTreeMap<String, String>), TurtleDocError> {
     let path: Option<Handle<JsString>> =
-        params.get_opt(cx, key_path).map_err(|e| TurtleDocError {
+        source.get_opt(cx, key_path).map_err(|e| TurtleDocError {
             message: e.to_string(),
         })?;
     let data: Option<Handle<JsValue>> =
-        params.get_opt(cx, key_data).map_err(|e| TurtleDocError {
+        source.get_opt(cx, key_data).map_err(|e| TurtleDocError {
             message: e.to_string(),
         })?;
 
@@ -501,52 +797,31 @@ fn make_doc<'a, 'b>(
         None
     };
 
-    // extract prefixes
-    let prefixes: Option<Handle<JsObject>> =
-        params
-            .get_opt(cx, PARAMS_EXTRA_PREFIXES)
-            .map_err(|e| TurtleDocError {
-                message: e.to_string(),
-            })?;
-
-    let mut prefixes_map = BTreeMap::new();
-    if let Some(prefixes) = prefixes {
-        // do
-
-        let properties = prefixes
-            .get_own_property_names(cx)
-            .and_then(|p| p.to_vec(cx))
-            .map_err(|e| TurtleDocError {
-                message: e.to_string(),
-            })?;
-
-        for property in properties {
-            if let Ok(property) = property.downcast::<JsString, _>(cx) {
-                let property = property.value(cx);
-                let value: Result<Option<Handle<JsString>>, _> =
-                    prefixes.get_opt(cx, property.as_str());
-                if let Ok(Some(value)) = value {
-                    let value = value.value(cx);
-                    prefixes_map.insert(property, value);
-                } else if let Err(e) = value {
-                    eprintln!("warning! value for {property} is incorrect. {e}");
-                }
-            } else {
-                eprintln!("warning! could not downcast property {property:?} to string");
-            }
-        }
-    }
+    let prefixes_map = read_extra_prefixes(params, cx)?;
     if let Some(path) = path {
         let path = path.value(cx);
         match PathBuf::from(&path).extension().and_then(|s| s.to_str()) {
             Some("json") => {
                 let triples = RdfJsonTriple::from_json_file(&path)?;
-                Ok(DocType::RdfJsonTriple((triples, prefixes_map)))
+                Ok((
+                    DocType::RdfJsonTriple((triples, prefixes_map.clone())),
+                    prefixes_map,
+                ))
             }
-            _ => {
-                let mut doc = TurtleDoc::from_file(path, well_known_prefix, buf)?;
-                doc.add_prefixes(prefixes_map)?;
-                Ok(DocType::TurtleDoc(doc))
+            extension => {
+                if !Path::new(&path).exists() || !matches!(extension, Some("ttl" | "n3")) {
+                    return Err(TurtleDocError {
+                        message: format!("file {path:?} doesn't exist or extension not n3/ttl"),
+                    });
+                }
+                // read here rather than by tortank, so the prefixes can be
+                // taken from the source
+                File::open(&path)
+                    .and_then(|mut file| file.read_to_string(buf))
+                    .map_err(|e| TurtleDocError {
+                        message: format!("cannot read file: {e}"),
+                    })?;
+                turtle_doc(buf, well_known_prefix, prefixes_map)
             }
         }
     } else if let Some(data) = data {
@@ -554,13 +829,13 @@ fn make_doc<'a, 'b>(
         if let Ok(data) = data.downcast::<JsString, _>(cx) {
             let data = data.value(cx);
             buf.push_str(&data);
-            match TurtleDoc::try_from((buf.as_str(), well_known_prefix)) {
-                Ok(mut doc) => {
-                    doc.add_prefixes(prefixes_map)?;
-                    Ok(DocType::TurtleDoc(doc))
-                }
-                Err(e) => match RdfJsonTriple::from_json(buf.as_str()) {
-                    Ok(rjt) => Ok(DocType::RdfJsonTriple((rjt, prefixes_map))),
+            match turtle_doc(buf, well_known_prefix, prefixes_map.clone()) {
+                Ok(doc) => Ok(doc),
+                Err(e) => match RdfJsonTriple::from_json(&data) {
+                    Ok(rjt) => Ok((
+                        DocType::RdfJsonTriple((rjt, prefixes_map.clone())),
+                        prefixes_map,
+                    )),
                     Err(e2) => Err(TurtleDocError {
                         message: format!("could not make doc from input:\n-{e}\n-{e2}"),
                     }),
@@ -578,14 +853,20 @@ fn make_doc<'a, 'b>(
                     })?;
                 triples.push(convert_neon_object_to_rdf_js_triple(cx, ja)?)
             }
-            Ok(DocType::RdfJsonTriple((triples, prefixes_map)))
+            Ok((
+                DocType::RdfJsonTriple((triples, prefixes_map.clone())),
+                prefixes_map,
+            ))
         } else if let Ok(data) = data.downcast::<JsObject, _>(cx) {
             let triples = convert_neon_object_to_rdf_js_triple(cx, data)?;
-            Ok(DocType::RdfJsonTriple((vec![triples], prefixes_map)))
+            Ok((
+                DocType::RdfJsonTriple((vec![triples], prefixes_map.clone())),
+                prefixes_map,
+            ))
         } else {
-            return Err(TurtleDocError {
+            Err(TurtleDocError {
                 message: "not implemented yet.".into(),
-            });
+            })
         }
     } else {
         Err(TurtleDocError {
@@ -593,3 +874,320 @@ fn make_doc<'a, 'b>(
         })
     }
 }
+
+/// parses the turtle source in buf, along with the prefixes it declares and
+/// the extra prefixes
+fn turtle_doc(
+    buf: &str,
+    well_known_prefix: Option<String>,
+    extra_prefixes: BTreeMap<String, String>,
+) -> Result<(DocType<'_>, BTreeMap<String, String>), TurtleDocError> {
+    let mut prefixes = prefix_declarations(buf);
+    prefixes.extend(extra_prefixes.clone());
+    let mut doc = TurtleDoc::try_from((buf, well_known_prefix))?;
+    doc.add_prefixes(extra_prefixes)?;
+    Ok((DocType::TurtleDoc(doc), prefixes))
+}
+
+/// base iri used to resolve the relative iris of a model: the base of its
+/// side (lhsBaseIri, rhsBaseIri or baseIri of an input), then baseIri, then
+/// the file url of its path
+pub(crate) fn read_base_iri<'a, C: Context<'a>>(
+    params: &Handle<JsObject>,
+    source: &Handle<JsObject>,
+    cx: &mut C,
+    key_path: &'static str,
+) -> Result<Option<String>, TurtleDocError> {
+    let to_error = |e: neon::result::Throw| TurtleDocError {
+        message: e.to_string(),
+    };
+    let side_key = match key_path {
+        PARAMS_LHS_PATH => PARAMS_LHS_BASE_IRI,
+        PARAMS_RHS_PATH => PARAMS_RHS_BASE_IRI,
+        _ => PARAMS_BASE_IRI,
+    };
+    let side_base: Option<Handle<JsString>> = source.get_opt(cx, side_key).map_err(to_error)?;
+    let base = match side_base {
+        Some(base) => Some(base.value(cx)),
+        None => params
+            .get_opt::<JsString, _, _>(cx, PARAMS_BASE_IRI)
+            .map_err(to_error)?
+            .map(|base| base.value(cx)),
+    };
+    if let Some(base) = base {
+        return match iri_error(&base) {
+            Some(e) => Err(TurtleDocError {
+                message: format!("invalid base iri <{base}>: {e}"),
+            }),
+            None => Ok(Some(base)),
+        };
+    }
+    let path: Option<Handle<JsString>> = source.get_opt(cx, key_path).map_err(to_error)?;
+    path.map(|path| file_iri(&path.value(cx))).transpose()
+}
+
+/// n3 serialization with iris relative to base, preceded by a base directive
+fn relativized_n3(
+    doc: &TurtleDoc,
+    base: &str,
+    prefixes: &BTreeMap<String, String>,
+) -> Result<String, TurtleDocError> {
+    let mut triples: Vec<RdfJsonTriple> = doc.into();
+    fn relativize(node: &mut RdfJsonNodeResult, base: &str) {
+        match node {
+            RdfJsonNodeResult::SingleNode(node) if node.typ == "uri" => {
+                node.value = relativize_iri(&node.value, base)
+            }
+            RdfJsonNodeResult::SingleNode(_) => {}
+            RdfJsonNodeResult::ListNodes(nodes) => {
+                nodes.iter_mut().for_each(|n| relativize(n, base))
+            }
+        }
+    }
+    for triple in triples.iter_mut() {
+        for node in [
+            &mut triple.subject,
+            &mut triple.predicate,
+            &mut triple.object,
+        ] {
+            relativize(node, base);
+        }
+    }
+    let relative = rdf_json_triple_to_doc(&triples, prefixes.clone())?;
+    Ok(format!("@base <{base}> .\n{relative}"))
+}
+
+/// extra prefixes provided by the caller, added to the output document
+pub(crate) fn read_extra_prefixes<'a, C: Context<'a>>(
+    params: &Handle<JsObject>,
+    cx: &mut C,
+) -> Result<BTreeMap<String, String>, TurtleDocError> {
+    let prefixes: Option<Handle<JsObject>> =
+        params
+            .get_opt(cx, PARAMS_EXTRA_PREFIXES)
+            .map_err(|e| TurtleDocError {
+                message: e.to_string(),
+            })?;
+
+    let mut prefixes_map = BTreeMap::new();
+    if let Some(prefixes) = prefixes {
+        // do
+
+        let properties = prefixes
+            .get_own_property_names(cx)
+            .and_then(|p| p.to_vec(cx))
+            .map_err(|e| TurtleDocError {
+                message: e.to_string(),
+            })?;
+
+        for property in properties {
+            if let Ok(property) = property.downcast::<JsString, _>(cx) {
+                let property = property.value(cx);
+                let value: Result<Option<Handle<JsString>>, _> =
+                    prefixes.get_opt(cx, property.as_str());
+                if let Ok(Some(value)) = value {
+                    let value = value.value(cx);
+                    prefixes_map.insert(property, value);
+                } else if let Err(e) = value {
+                    eprintln!("warning! value for {property} is incorrect. {e}");
+                }
+            } else {
+                eprintln!("warning! could not downcast property {property:?} to string");
+            }
+        }
+    }
+    Ok(prefixes_map)
+}
+
+/// checks whether an input was provided for the given keys, for operations
+/// where a model is optional
+pub(crate) fn has_input<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    key_path: &'static str,
+    key_data: &'static str,
+) -> NeonResult<bool> {
+    let path = params.get_opt::<JsValue, _, _>(cx, key_path)?;
+    let data = params.get_opt::<JsValue, _, _>(cx, key_data)?;
+    Ok(path.is_some() || data.is_some())
+}
+
+/// loads a model with make_doc and converts it to owned triples, its
+/// prefixes are added to prefixes like make_doc does
+pub(crate) fn load_triples<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    prefixes: &mut BTreeMap<String, String>,
+    key_path: &'static str,
+    key_data: &'static str,
+) -> Result<Vec<Triple>, TurtleDocError> {
+    load_triples_from(params, params, cx, prefixes, key_path, key_data)
+}
+
+fn load_triples_from<'a>(
+    params: &Handle<'a, JsObject>,
+    source: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    prefixes: &mut BTreeMap<String, String>,
+    key_path: &'static str,
+    key_data: &'static str,
+) -> Result<Vec<Triple>, TurtleDocError> {
+    let mut buf = String::new();
+    match make_doc_from(params, source, cx, &mut buf, prefixes, key_path, key_data)? {
+        DocType::TurtleDoc(doc) => doc_to_triples(&doc),
+        DocType::RdfJsonTriple((rjs, _)) => rdf_json_to_triples(&rjs[..]),
+    }
+}
+
+/// loads every element of inputs, or returns None when inputs is not provided
+fn load_inputs<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    prefixes: &mut BTreeMap<String, String>,
+) -> Result<Option<Vec<Vec<Triple>>>, TurtleDocError> {
+    let inputs: Option<Handle<JsArray>> =
+        params
+            .get_opt(cx, PARAMS_INPUTS)
+            .map_err(|e| TurtleDocError {
+                message: e.to_string(),
+            })?;
+    let Some(inputs) = inputs else {
+        return Ok(None);
+    };
+    let inputs = inputs.to_vec(cx).map_err(|e| TurtleDocError {
+        message: e.to_string(),
+    })?;
+    if inputs.is_empty() {
+        return Err(TurtleDocError {
+            message: format!("'{PARAMS_INPUTS}' must not be empty"),
+        });
+    }
+    let mut models = Vec::with_capacity(inputs.len());
+    for (idx, input) in inputs.into_iter().enumerate() {
+        let input: Handle<JsObject> =
+            input
+                .downcast::<JsObject, _>(cx)
+                .map_err(|e| TurtleDocError {
+                    message: format!("input {idx}: {e}"),
+                })?;
+        let triples = load_triples_from(
+            params,
+            &input,
+            cx,
+            prefixes,
+            PARAMS_INPUT_PATH,
+            PARAMS_INPUT_DATA,
+        )
+        .map_err(|e| TurtleDocError {
+            message: format!("input {idx}: {}", e.message),
+        })?;
+        models.push(triples);
+    }
+    Ok(Some(models))
+}
+
+fn doc_to_triples(doc: &TurtleDoc) -> Result<Vec<Triple>, TurtleDocError> {
+    let json_triples: Vec<RdfJsonTriple> = doc.into();
+    triples_from_rdf_json(&json_triples)
+}
+
+/// goes through tortank statements so literals are normalized the same way
+/// as when they are parsed
+pub(crate) fn rdf_json_to_triples(
+    triples: &[RdfJsonTriple],
+) -> Result<Vec<Triple>, TurtleDocError> {
+    let doc = rdf_json_triple_to_doc(triples, BTreeMap::new())?;
+    doc_to_triples(&doc)
+}
+
+pub(crate) fn turtle_to_triples(data: &str) -> Result<Vec<Triple>, TurtleDocError> {
+    let doc = TurtleDoc::try_from((data, None))?;
+    doc_to_triples(&doc)
+}
+
+pub(crate) fn make_triples_response<'a, C: Context<'a>>(
+    params: &Handle<JsObject>,
+    cx: &mut C,
+    triples: &[Triple],
+    prefixes: &BTreeMap<String, String>,
+) -> JsResult<'a, JsValue> {
+    let json_triples: Vec<RdfJsonTriple> = triples.iter().map(Into::into).collect();
+    match rdf_json_triple_to_doc(&json_triples[..], BTreeMap::new()) {
+        Ok(doc) => make_response(params, cx, doc, prefixes),
+        Err(e) => cx.throw_error(e.message),
+    }
+}
+
+/// normalizes a literal constant, e.g from a query, the same way tortank
+/// normalizes parsed literals so they compare equal
+pub(crate) fn normalize_literal(term: Term) -> Term {
+    if !matches!(term, Term::Literal { .. }) {
+        return term;
+    }
+    let triple = Triple {
+        subject: Term::Iri("urn:x".into()),
+        predicate: Term::Iri("urn:x".into()),
+        object: term,
+    };
+    match rdf_json_to_triples(&[(&triple).into()]) {
+        Ok(mut triples) if triples.len() == 1 => triples.remove(0).object,
+        _ => triple.object,
+    }
+}
+
+/// writes the model to a temporary file next to path, then renames it over
+/// path so the file is never left half written. the format follows the
+/// extension of path, like make_doc.
+pub(crate) fn write_triples_in_place(
+    params: &Handle<JsObject>,
+    cx: &mut FunctionContext,
+    triples: &[Triple],
+    path: &str,
+) -> Result<(), TurtleDocError> {
+    let buf_size: Option<usize> = params
+        .get_opt::<JsNumber, _, _>(cx, PARAMS_BUF_SIZE)
+        .map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?
+        .map(|b| b.value(cx).abs() as usize)
+        .filter(|b| *b > 0);
+    let json = PathBuf::from(path).extension().and_then(|s| s.to_str()) == Some("json");
+    let json_triples: Vec<RdfJsonTriple> = triples.iter().map(Into::into).collect();
+    let doc = rdf_json_triple_to_doc(&json_triples[..], BTreeMap::new())?;
+    let tmp_path = format!("{path}.{}.tmp", std::process::id());
+    let written = doc
+        .to_file(&tmp_path, buf_size.or(Some(doc.len().max(1))), json)
+        .and_then(|_| {
+            std::fs::rename(&tmp_path, path).map_err(|e| TurtleDocError {
+                message: e.to_string(),
+            })
+        });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    written
+}
+
+/// loads the lhs model and keeps the statements matching the subject,
+/// predicate and object params, like statements
+pub(crate) fn load_filtered_triples<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+) -> Result<Vec<Triple>, TurtleDocError> {
+    let mut buf = String::new();
+    let mut prefixes = BTreeMap::new();
+    let doc_type = make_doc(
+        params,
+        cx,
+        &mut buf,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    )?;
+    let doc = match doc_type {
+        DocType::TurtleDoc(doc) => doc,
+        DocType::RdfJsonTriple((ref rjs, prefixes)) => rdf_json_triple_to_doc(&rjs[..], prefixes)?,
+    };
+    let doc = filter_statements(params, cx, &doc, &prefixes)?;
+    doc_to_triples(&doc)
+}
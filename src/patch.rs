@@ -0,0 +1,570 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::{RdfJsonTriple, TurtleDocError};
+
+use crate::{
+    graph::{canonicalize_blank_nodes, Term, Triple},
+    obj::{
+        convert_neon_object_to_rdf_js_triple, load_triples, make_js_array, make_triples_response,
+        rdf_json_to_triples, turtle_to_triples, PARAMS_LHS_DATA, PARAMS_LHS_PATH,
+        PARAMS_MAPPER_FUNCTION, PARAMS_OUTPUT_FILE_PATH, PARAMS_OUTPUT_TYPE, PARAMS_RHS_DATA,
+        PARAMS_RHS_PATH,
+    },
+};
+
+const OUTPUT_TYPE_RDF_PATCH: &str = "rdf-patch";
+const OUTPUT_TYPE_SPARQL: &str = "sparql";
+const OUTPUT_TYPE_N3: &str = "n3";
+
+const PARAMS_PATCH: &str = "patch";
+const PARAMS_STRICT: &str = "strict";
+const PARAMS_RELABEL_BLANK_NODES: &str = "relabelBlankNodes";
+
+const KEY_ADDED: &str = "added";
+const KEY_REMOVED: &str = "removed";
+
+pub enum PatchOperation {
+    Add(Vec<Triple>),
+    Delete(Vec<Triple>),
+}
+
+pub struct Patch {
+    pub added: Vec<Triple>,
+    pub removed: Vec<Triple>,
+}
+
+impl Patch {
+    /// computes what must be removed from lhs and added to it to get rhs
+    pub fn between(lhs: &[Triple], rhs: &[Triple]) -> Patch {
+        let lhs_set: HashSet<&Triple> = lhs.iter().collect();
+        let rhs_set: HashSet<&Triple> = rhs.iter().collect();
+        Patch {
+            added: rhs
+                .iter()
+                .filter(|t| !lhs_set.contains(t))
+                .cloned()
+                .collect(),
+            removed: lhs
+                .iter()
+                .filter(|t| !rhs_set.contains(t))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn to_rdf_patch(&self) -> String {
+        let mut out = String::from("TX .\n");
+        for triple in &self.removed {
+            out.push_str(&format!("D {triple}\n"));
+        }
+        for triple in &self.added {
+            out.push_str(&format!("A {triple}\n"));
+        }
+        out.push_str("TC .\n");
+        out
+    }
+
+    pub fn to_sparql_update(&self) -> Result<String, String> {
+        if let Some(t) = self.removed.iter().find(|t| {
+            matches!(t.subject, Term::BlankNode(_)) || matches!(t.object, Term::BlankNode(_))
+        }) {
+            return Err(format!(
+                "blank nodes are not allowed in DELETE DATA, cannot remove {t}"
+            ));
+        }
+        Ok(format!(
+            "DELETE DATA {{\n{}}} ;\nINSERT DATA {{\n{}}}\n",
+            to_ntriples(&self.removed, "  "),
+            to_ntriples(&self.added, "  ")
+        ))
+    }
+}
+
+fn to_ntriples(triples: &[Triple], indent: &str) -> String {
+    triples.iter().map(|t| format!("{indent}{t}\n")).collect()
+}
+
+pub fn diff(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+
+    let output_type: Option<String> = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_OUTPUT_TYPE)?
+        .map(|o| o.value(&mut cx));
+    let output_file_path: Option<String> = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_OUTPUT_FILE_PATH)?
+        .map(|o| o.value(&mut cx));
+    let js_mapper_func = params.get_opt::<JsFunction, _, _>(&mut cx, PARAMS_MAPPER_FUNCTION)?;
+
+    let mut prefixes = BTreeMap::new();
+    let lhs = load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    );
+    let rhs = load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_RHS_PATH,
+        PARAMS_RHS_DATA,
+    );
+    let (lhs, rhs) = match (lhs, rhs) {
+        (Ok(lhs), Ok(rhs)) => (lhs, rhs),
+        (Ok(_), Err(e)) | (Err(e), Ok(_)) => return cx.throw_error(e.message),
+        (Err(e1), Err(e2)) => {
+            return cx.throw_error(format!("error:\n-{}\n-{}", e1.message, e2.message))
+        }
+    };
+    // generated blank node labels differ between two parsings of the same
+    // model. canonical labels only depend on the statements of the blank node
+    // and of the blank nodes linked to it, so unchanged anonymous nodes are
+    // not reported even when other anonymous nodes are added or removed
+    let patch = Patch::between(
+        &canonicalize_blank_nodes(&lhs),
+        &canonicalize_blank_nodes(&rhs),
+    );
+
+    let serialized = match output_type.as_deref() {
+        Some(OUTPUT_TYPE_SPARQL) => match patch.to_sparql_update() {
+            Ok(update) => Some(update),
+            Err(e) => return cx.throw_error(e),
+        },
+        Some(OUTPUT_TYPE_RDF_PATCH) => Some(patch.to_rdf_patch()),
+        Some(o) if output_file_path.is_some() => {
+            return cx.throw_error(format!(
+                "cannot write a {o} diff to a file, use {OUTPUT_TYPE_RDF_PATCH} or {OUTPUT_TYPE_SPARQL}"
+            ))
+        }
+        None if output_file_path.is_some() => Some(patch.to_rdf_patch()),
+        _ => None,
+    };
+
+    if let Some(opf) = output_file_path {
+        let serialized = serialized.unwrap_or_default();
+        return match std::fs::write(opf, serialized) {
+            Ok(_) => Ok(cx.boolean(true).upcast()),
+            Err(e) => cx.throw_error(e.to_string()),
+        };
+    }
+    if let Some(serialized) = serialized {
+        return Ok(cx.string(serialized).upcast());
+    }
+
+    let obj = cx.empty_object();
+    if output_type.as_deref() == Some(OUTPUT_TYPE_N3) {
+        let added = cx.string(to_ntriples(&patch.added, ""));
+        let removed = cx.string(to_ntriples(&patch.removed, ""));
+        obj.set(&mut cx, KEY_ADDED, added)?;
+        obj.set(&mut cx, KEY_REMOVED, removed)?;
+    } else {
+        let added = make_js_array(
+            &mut cx,
+            patch.added.iter().map(RdfJsonTriple::from).collect(),
+            js_mapper_func,
+            None,
+        )?;
+        let removed = make_js_array(
+            &mut cx,
+            patch.removed.iter().map(RdfJsonTriple::from).collect(),
+            js_mapper_func,
+            None,
+        )?;
+        obj.set(&mut cx, KEY_ADDED, added)?;
+        obj.set(&mut cx, KEY_REMOVED, removed)?;
+    }
+    Ok(obj.upcast())
+}
+
+pub fn apply_patch(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let strict = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_STRICT)?
+        .map(|s| s.value(&mut cx))
+        .unwrap_or(false);
+    let relabel_blank_nodes = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_RELABEL_BLANK_NODES)?
+        .map(|s| s.value(&mut cx));
+
+    let mut prefixes = BTreeMap::new();
+    let model = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(model) => model,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let operations = match read_patch(&params, &mut cx) {
+        Ok(operations) => operations,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    // same relabeling as diff, so a patch made by diff applies on the model
+    // it was computed from. by default only when the patch has such labels
+    let model = if relabel_blank_nodes.unwrap_or_else(|| has_canonical_labels(&operations)) {
+        canonicalize_blank_nodes(&model)
+    } else {
+        model
+    };
+    match apply(model, operations, strict) {
+        Ok(model) => make_triples_response(&params, &mut cx, &model, &prefixes),
+        Err(e) => cx.throw_error(e.message),
+    }
+}
+
+/// true when a blank node of the patch has a label given by diff
+fn has_canonical_labels(operations: &[PatchOperation]) -> bool {
+    operations
+        .iter()
+        .flat_map(|op| match op {
+            PatchOperation::Add(triples) | PatchOperation::Delete(triples) => triples.iter(),
+        })
+        .flat_map(|t| [&t.subject, &t.object])
+        .any(|term| matches!(term, Term::BlankNode(label) if label.starts_with("c14n")))
+}
+
+/// applies the operations in order, consecutive operations of the same kind
+/// as one. the model is indexed once for the whole patch
+pub fn apply(
+    mut model: Vec<Triple>,
+    operations: Vec<PatchOperation>,
+    strict: bool,
+) -> Result<Vec<Triple>, TurtleDocError> {
+    // position of each statement, a deleted statement is only dropped from
+    // the model at the end
+    let mut positions: HashMap<Triple, usize> = HashMap::with_capacity(model.len());
+    for (idx, triple) in model.iter().enumerate() {
+        positions.entry(triple.clone()).or_insert(idx);
+    }
+    let mut operations = operations.into_iter().peekable();
+    while let Some(operation) = operations.next() {
+        match operation {
+            PatchOperation::Add(mut triples) => {
+                while let Some(PatchOperation::Add(next)) =
+                    operations.next_if(|op| matches!(op, PatchOperation::Add(_)))
+                {
+                    triples.extend(next);
+                }
+                for triple in triples {
+                    if !positions.contains_key(&triple) {
+                        positions.insert(triple.clone(), model.len());
+                        model.push(triple);
+                    }
+                }
+            }
+            PatchOperation::Delete(mut triples) => {
+                while let Some(PatchOperation::Delete(next)) =
+                    operations.next_if(|op| matches!(op, PatchOperation::Delete(_)))
+                {
+                    triples.extend(next);
+                }
+                for triple in triples {
+                    if positions.remove(&triple).is_none() && strict {
+                        return Err(TurtleDocError {
+                            message: format!("cannot delete {triple}, statement not in the model"),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    let mut idx = 0;
+    model.retain(|t| {
+        let live = positions.get(t) == Some(&idx);
+        idx += 1;
+        live
+    });
+    Ok(model)
+}
+
+fn read_patch<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+) -> Result<Vec<PatchOperation>, TurtleDocError> {
+    let patch: Handle<JsValue> = params
+        .get_opt(cx, PARAMS_PATCH)
+        .map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?
+        .ok_or_else(|| TurtleDocError {
+            message: format!("missing patch ('{PARAMS_PATCH}')"),
+        })?;
+
+    if let Ok(patch) = patch.downcast::<JsString, _>(cx) {
+        let patch = patch.value(cx);
+        if is_sparql_update(&patch) {
+            parse_sparql_data_update(&patch)
+        } else {
+            parse_rdf_patch(&patch)
+        }
+    } else if let Ok(patch) = patch.downcast::<JsObject, _>(cx) {
+        let removed = read_js_triples(cx, patch, KEY_REMOVED)?;
+        let added = read_js_triples(cx, patch, KEY_ADDED)?;
+        Ok(vec![
+            PatchOperation::Delete(removed),
+            PatchOperation::Add(added),
+        ])
+    } else {
+        Err(TurtleDocError {
+            message: format!("'{PARAMS_PATCH}' must be a string or an object {{added, removed}}"),
+        })
+    }
+}
+
+fn read_js_triples<'a>(
+    cx: &mut FunctionContext<'a>,
+    patch: Handle<'a, JsObject>,
+    key: &str,
+) -> Result<Vec<Triple>, TurtleDocError> {
+    let array: Option<Handle<JsArray>> = patch.get_opt(cx, key).map_err(|e| TurtleDocError {
+        message: e.to_string(),
+    })?;
+    let Some(array) = array else {
+        return Ok(vec![]);
+    };
+    let js_arr = array.to_vec(cx).map_err(|e| TurtleDocError {
+        message: e.to_string(),
+    })?;
+    let mut triples = Vec::with_capacity(js_arr.len());
+    for ja in js_arr {
+        let ja: Handle<JsObject> = ja.downcast::<JsObject, _>(cx).map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?;
+        triples.push(convert_neon_object_to_rdf_js_triple(cx, ja)?);
+    }
+    rdf_json_to_triples(&triples[..])
+}
+
+fn is_sparql_update(patch: &str) -> bool {
+    patch
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .and_then(|l| l.split_whitespace().next())
+        .map(|keyword| {
+            ["INSERT", "DELETE", "PREFIX", "BASE"]
+                .iter()
+                .any(|k| k.eq_ignore_ascii_case(keyword))
+        })
+        .unwrap_or(false)
+}
+
+fn turtle_header(base: Option<&str>, prefixes: &BTreeMap<String, String>) -> String {
+    let mut header = String::new();
+    if let Some(base) = base {
+        header.push_str(&format!("@base <{base}> .\n"));
+    }
+    for (k, v) in prefixes {
+        header.push_str(&format!("@prefix {k}: <{v}> .\n"));
+    }
+    header
+}
+
+/// parses an RDF Patch document (https://afs.github.io/rdf-patch/).
+/// a transaction aborted with TA discards the changes made since TX.
+pub fn parse_rdf_patch(patch: &str) -> Result<Vec<PatchOperation>, TurtleDocError> {
+    let mut prefixes = BTreeMap::new();
+    let mut operations = vec![];
+    let mut transaction_start = None;
+    for (idx, line) in patch.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (code, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let error = |message: String| TurtleDocError {
+            message: format!("line {}: {message}", idx + 1),
+        };
+        match code {
+            "H" => {}
+            "TX" => transaction_start = Some(operations.len()),
+            "TC" => transaction_start = None,
+            "TA" => {
+                let start = transaction_start
+                    .take()
+                    .ok_or_else(|| error("TA without TX".into()))?;
+                operations.truncate(start);
+            }
+            "PA" => {
+                let rest = rest.trim_end_matches('.').trim();
+                let (prefix, iri) = rest
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| error(format!("invalid prefix {rest}")))?;
+                let iri = iri.trim().trim_start_matches('<').trim_end_matches('>');
+                prefixes.insert(prefix.trim_end_matches(':').to_string(), iri.to_string());
+            }
+            "PD" => {
+                let prefix = rest.trim_end_matches('.').trim().trim_end_matches(':');
+                prefixes.remove(prefix);
+            }
+            "A" | "D" => {
+                let statement = if rest.ends_with('.') {
+                    rest.to_string()
+                } else {
+                    format!("{rest} .")
+                };
+                let triples =
+                    turtle_to_triples(&format!("{}{statement}", turtle_header(None, &prefixes)))
+                        .map_err(|e| error(e.message))?;
+                if code == "A" {
+                    operations.push(PatchOperation::Add(triples));
+                } else {
+                    operations.push(PatchOperation::Delete(triples));
+                }
+            }
+            c => return Err(error(format!("unknown code {c}"))),
+        }
+    }
+    Ok(operations)
+}
+
+/// parses a sequence of INSERT DATA / DELETE DATA operations
+pub fn parse_sparql_data_update(update: &str) -> Result<Vec<PatchOperation>, TurtleDocError> {
+    let mut prefixes = BTreeMap::new();
+    let mut base = None;
+    let mut operations = vec![];
+    let mut rest = update;
+    loop {
+        rest = skip_ws_and_comments(rest);
+        if rest.is_empty() {
+            break;
+        }
+        if let Some(r) = rest.strip_prefix(';') {
+            rest = r;
+            continue;
+        }
+        let (keyword, r) = next_word(rest);
+        match keyword.to_ascii_uppercase().as_str() {
+            "PREFIX" => {
+                let (prefix, r) = next_word(skip_ws_and_comments(r));
+                let (iri, r) = read_iri(skip_ws_and_comments(r))?;
+                prefixes.insert(prefix.trim_end_matches(':').to_string(), iri.to_string());
+                rest = r;
+            }
+            "BASE" => {
+                let (iri, r) = read_iri(skip_ws_and_comments(r))?;
+                base = Some(iri.to_string());
+                rest = r;
+            }
+            op @ ("INSERT" | "DELETE") => {
+                let (data, r) = next_word(skip_ws_and_comments(r));
+                if !data.eq_ignore_ascii_case("DATA") {
+                    return Err(TurtleDocError {
+                        message: format!("only {op} DATA is supported"),
+                    });
+                }
+                let (block, r) = read_block(skip_ws_and_comments(r))?;
+                let triples = if block.trim().is_empty() {
+                    vec![]
+                } else {
+                    turtle_to_triples(&format!(
+                        "{}{block}",
+                        turtle_header(base.as_deref(), &prefixes)
+                    ))?
+                };
+                if op == "INSERT" {
+                    operations.push(PatchOperation::Add(triples));
+                } else {
+                    operations.push(PatchOperation::Delete(triples));
+                }
+                rest = r;
+            }
+            k => {
+                return Err(TurtleDocError {
+                    message: format!("unexpected token {k}"),
+                })
+            }
+        }
+    }
+    Ok(operations)
+}
+
+fn skip_ws_and_comments(s: &str) -> &str {
+    let mut s = s.trim_start();
+    while s.starts_with('#') {
+        s = s
+            .split_once('\n')
+            .map(|(_, r)| r)
+            .unwrap_or("")
+            .trim_start();
+    }
+    s
+}
+
+fn next_word(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| c.is_whitespace() || c == '{' || c == '<' || c == ';')
+        .unwrap_or(s.len());
+    (&s[..end], &s[end..])
+}
+
+fn read_iri(s: &str) -> Result<(&str, &str), TurtleDocError> {
+    s.strip_prefix('<')
+        .and_then(|s| s.split_once('>'))
+        .ok_or_else(|| TurtleDocError {
+            message: format!("expected an iri, got {s}"),
+        })
+}
+
+/// length of the string literal at the start of s, escapes included
+fn string_literal_len(s: &str) -> Option<usize> {
+    let quote = [r#"""""#, "'''", "\"", "'"]
+        .into_iter()
+        .find(|q| s.starts_with(q))?;
+    let mut idx = quote.len();
+    while idx < s.len() {
+        let rest = &s[idx..];
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            idx += 1 + escaped.chars().next().map(char::len_utf8).unwrap_or(0);
+        } else if rest.starts_with(quote) {
+            return Some(idx + quote.len());
+        } else {
+            idx += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        }
+    }
+    None
+}
+
+/// returns the content between braces, skipping over strings, iris and comments
+fn read_block(s: &str) -> Result<(&str, &str), TurtleDocError> {
+    let unterminated = || TurtleDocError {
+        message: "unterminated block".into(),
+    };
+    let body = s.strip_prefix('{').ok_or_else(|| TurtleDocError {
+        message: format!("expected {{, got {s}"),
+    })?;
+    let mut depth = 1;
+    let mut idx = 0;
+    while let Some(c) = body[idx..].chars().next() {
+        let rest = &body[idx..];
+        match c {
+            '"' | '\'' => {
+                idx += string_literal_len(rest).ok_or_else(unterminated)?;
+                continue;
+            }
+            '<' => {
+                idx += rest.find('>').ok_or_else(unterminated)? + 1;
+                continue;
+            }
+            '#' => {
+                idx += rest.find('\n').unwrap_or(rest.len());
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok((&body[..idx], &body[idx + 1..]));
+                }
+            }
+            _ => {}
+        }
+        idx += c.len_utf8();
+    }
+    Err(unterminated())
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+use crate::{
+    graph::{Graph, Term, Triple, RDF_TYPE},
+    obj::{
+        has_input, load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH,
+        PARAMS_RHS_DATA, PARAMS_RHS_PATH,
+    },
+};
+
+const PARAMS_ENTAILMENT: &str = "entailment";
+const PARAMS_INFERRED_ONLY: &str = "inferredOnly";
+
+const ENTAILMENT_RDFS: &str = "rdfs";
+
+const RDFS_SUB_CLASS_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+const RDFS_SUB_PROPERTY_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subPropertyOf";
+const RDFS_DOMAIN: &str = "http://www.w3.org/2000/01/rdf-schema#domain";
+const RDFS_RANGE: &str = "http://www.w3.org/2000/01/rdf-schema#range";
+
+/// triples entailed by the model and the ontology with the rdfs rules 2, 3,
+/// 5, 7, 9 and 11, that are neither in the model nor in the ontology. each
+/// triple is joined with the triples already known when it is added, so
+/// every rule fires whatever the order of its premises.
+pub(crate) fn rdfs_closure(model: &[Triple], ontology: &[Triple]) -> Vec<Triple> {
+    let [rdf_type, sub_class_of, sub_property_of, domain, range] = [
+        RDF_TYPE,
+        RDFS_SUB_CLASS_OF,
+        RDFS_SUB_PROPERTY_OF,
+        RDFS_DOMAIN,
+        RDFS_RANGE,
+    ]
+    .map(|iri| Term::Iri(iri.into()));
+    let mut graph = Graph::new(model.iter().chain(ontology).cloned());
+    let mut queue: Vec<Triple> = model.iter().chain(ontology).cloned().collect();
+    let mut inferred = vec![];
+    let triple = |subject: &Term, predicate: &Term, object: &Term| Triple {
+        subject: subject.clone(),
+        predicate: predicate.clone(),
+        object: object.clone(),
+    };
+    while let Some(t) = queue.pop() {
+        let (s, p, o) = (&t.subject, &t.predicate, &t.object);
+        let mut derived = vec![];
+        // the triple as data
+        for schema in graph.matching(Some(p), Some(&domain), None) {
+            derived.push(triple(s, &rdf_type, &schema.object));
+        }
+        if !matches!(o, Term::Literal { .. }) {
+            for schema in graph.matching(Some(p), Some(&range), None) {
+                derived.push(triple(o, &rdf_type, &schema.object));
+            }
+        }
+        for schema in graph.matching(Some(p), Some(&sub_property_of), None) {
+            derived.push(triple(s, &schema.object, o));
+        }
+        if p == &rdf_type {
+            for schema in graph.matching(Some(o), Some(&sub_class_of), None) {
+                derived.push(triple(s, &rdf_type, &schema.object));
+            }
+        }
+        // the triple as schema
+        if p == &domain || p == &range {
+            for data in graph.matching(None, Some(s), None) {
+                let node = if p == &domain {
+                    &data.subject
+                } else {
+                    &data.object
+                };
+                if !matches!(node, Term::Literal { .. }) {
+                    derived.push(triple(node, &rdf_type, o));
+                }
+            }
+        }
+        if p == &sub_property_of {
+            for data in graph.matching(None, Some(s), None) {
+                derived.push(triple(&data.subject, o, &data.object));
+            }
+        }
+        if p == &sub_class_of {
+            for data in graph.matching(None, Some(&rdf_type), Some(s)) {
+                derived.push(triple(&data.subject, &rdf_type, o));
+            }
+        }
+        if p == &sub_class_of || p == &sub_property_of {
+            for next in graph.matching(Some(o), Some(p), None) {
+                derived.push(triple(s, p, &next.object));
+            }
+            for previous in graph.matching(None, Some(p), Some(s)) {
+                derived.push(triple(&previous.subject, p, o));
+            }
+        }
+        for derived in derived {
+            if graph.insert(derived.clone()) {
+                queue.push(derived.clone());
+                inferred.push(derived);
+            }
+        }
+    }
+    inferred
+}
+
+/// the lhs model with the triples entailed by it and the optional rhs
+/// ontology, or None when no entailment is requested. the prefixes of the
+/// models are added to prefixes
+pub(crate) fn load_entailed_triples<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    prefixes: &mut BTreeMap<String, String>,
+) -> Result<Option<Vec<Triple>>, TurtleDocError> {
+    let entailment: Option<String> = params
+        .get_opt::<JsString, _, _>(cx, PARAMS_ENTAILMENT)
+        .map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?
+        .map(|e| e.value(cx));
+    match entailment.as_deref() {
+        None => Ok(None),
+        Some(ENTAILMENT_RDFS) => {
+            let (mut model, inferred) = load_and_infer(params, cx, prefixes)?;
+            model.extend(inferred);
+            Ok(Some(model))
+        }
+        Some(e) => Err(TurtleDocError {
+            message: format!("entailment {e} not supported, expected '{ENTAILMENT_RDFS}'"),
+        }),
+    }
+}
+
+fn load_and_infer<'a>(
+    params: &Handle<'a, JsObject>,
+    cx: &mut FunctionContext<'a>,
+    prefixes: &mut BTreeMap<String, String>,
+) -> Result<(Vec<Triple>, Vec<Triple>), TurtleDocError> {
+    let model = load_triples(params, cx, prefixes, PARAMS_LHS_PATH, PARAMS_LHS_DATA)?;
+    let has_ontology =
+        has_input(params, cx, PARAMS_RHS_PATH, PARAMS_RHS_DATA).map_err(|e| TurtleDocError {
+            message: e.to_string(),
+        })?;
+    let ontology = if has_ontology {
+        load_triples(params, cx, prefixes, PARAMS_RHS_PATH, PARAMS_RHS_DATA)?
+    } else {
+        vec![]
+    };
+    let inferred = rdfs_closure(&model, &ontology);
+    Ok((model, inferred))
+}
+
+pub fn infer(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let inferred_only = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_INFERRED_ONLY)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let mut prefixes = BTreeMap::new();
+    let (mut model, inferred) = match load_and_infer(&params, &mut cx, &mut prefixes) {
+        Ok(res) => res,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    if inferred_only {
+        return make_triples_response(&params, &mut cx, &inferred, &prefixes);
+    }
+    model.extend(inferred);
+    make_triples_response(&params, &mut cx, &model, &prefixes)
+}
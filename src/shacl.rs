@@ -0,0 +1,520 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+use crate::{
+    graph::{Graph, Term, Triple, RDF_LANG_STRING, RDF_TYPE, XSD_BOOLEAN, XSD_STRING},
+    obj::{
+        load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH, PARAMS_RHS_DATA,
+        PARAMS_RHS_PATH,
+    },
+    sparql::{compile_regex, numeric_value, PathEvaluator, PropertyPath},
+};
+
+const SH: &str = "http://www.w3.org/ns/shacl#";
+const RDF_FIRST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#first";
+const RDF_REST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#rest";
+const RDFS_CLASS: &str = "http://www.w3.org/2000/01/rdf-schema#Class";
+const RDFS_SUB_CLASS_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+
+/// sh:node can refer to shapes recursively, deeper shapes are considered valid
+const MAX_SHAPE_DEPTH: usize = 32;
+
+const TARGETS: [&str; 4] = [
+    "targetClass",
+    "targetNode",
+    "targetSubjectsOf",
+    "targetObjectsOf",
+];
+
+/// value range constraints and the ordering that violates them
+const RANGES: [(&str, &str, &[Ordering]); 4] = [
+    (
+        "minInclusive",
+        "MinInclusiveConstraintComponent",
+        &[Ordering::Less],
+    ),
+    (
+        "maxInclusive",
+        "MaxInclusiveConstraintComponent",
+        &[Ordering::Greater],
+    ),
+    (
+        "minExclusive",
+        "MinExclusiveConstraintComponent",
+        &[Ordering::Less, Ordering::Equal],
+    ),
+    (
+        "maxExclusive",
+        "MaxExclusiveConstraintComponent",
+        &[Ordering::Greater, Ordering::Equal],
+    ),
+];
+
+fn sh(local: &str) -> Term {
+    Term::Iri(format!("{SH}{local}"))
+}
+
+struct ValidationResult {
+    focus: Term,
+    path: Option<Term>,
+    value: Option<Term>,
+    shape: Term,
+    component: &'static str,
+    severity: Term,
+    message: Option<Term>,
+}
+
+struct Validator<'a> {
+    data: &'a Graph,
+    shapes: &'a Graph,
+}
+
+impl<'a> Validator<'a> {
+    fn objects(&self, subject: &Term, predicate: &str) -> Vec<Term> {
+        self.shapes
+            .matching(Some(subject), Some(&sh(predicate)), None)
+            .map(|t| t.object.clone())
+            .collect()
+    }
+
+    fn object(&self, subject: &Term, predicate: &str) -> Option<Term> {
+        self.objects(subject, predicate).into_iter().next()
+    }
+
+    /// members of an rdf list of the shapes graph
+    fn list(&self, mut head: Term) -> Vec<Term> {
+        let (first, rest) = (Term::Iri(RDF_FIRST.into()), Term::Iri(RDF_REST.into()));
+        let mut members = vec![];
+        let mut visited = HashSet::new();
+        while visited.insert(head.clone()) {
+            let Some(member) = self.shapes.matching(Some(&head), Some(&first), None).next() else {
+                break;
+            };
+            members.push(member.object.clone());
+            let next = self
+                .shapes
+                .matching(Some(&head), Some(&rest), None)
+                .next()
+                .map(|t| t.object.clone());
+            match next {
+                Some(next) => head = next,
+                None => break,
+            }
+        }
+        members
+    }
+
+    /// shacl property path, e.g a predicate, a list (sequence) or a blank
+    /// node with sh:inversePath, sh:alternativePath, sh:zeroOrMorePath...
+    fn path(&self, path: &Term) -> Result<PropertyPath, TurtleDocError> {
+        if let Term::Iri(_) = path {
+            return Ok(PropertyPath::Predicate(path.clone()));
+        }
+        let nested = |key: &str| self.object(path, key).map(|p| self.path(&p));
+        if let Some(inverse) = nested("inversePath") {
+            return Ok(PropertyPath::Inverse(Box::new(inverse?)));
+        }
+        if let Some(path) = nested("zeroOrMorePath") {
+            return Ok(PropertyPath::ZeroOrMore(Box::new(path?)));
+        }
+        if let Some(path) = nested("oneOrMorePath") {
+            return Ok(PropertyPath::OneOrMore(Box::new(path?)));
+        }
+        if let Some(path) = nested("zeroOrOnePath") {
+            return Ok(PropertyPath::ZeroOrOne(Box::new(path?)));
+        }
+        let (members, alternative) = match self.object(path, "alternativePath") {
+            Some(list) => (self.list(list), true),
+            None => (self.list(path.clone()), false),
+        };
+        members
+            .iter()
+            .map(|member| self.path(member))
+            .reduce(|lhs, rhs| {
+                let (lhs, rhs) = (Box::new(lhs?), Box::new(rhs?));
+                Ok(if alternative {
+                    PropertyPath::Alternative(lhs, rhs)
+                } else {
+                    PropertyPath::Sequence(lhs, rhs)
+                })
+            })
+            .unwrap_or_else(|| {
+                Err(TurtleDocError {
+                    message: format!("invalid path {path}"),
+                })
+            })
+    }
+
+    /// shapes with explicit targets, or that are also a class
+    fn shapes_with_targets(&self) -> Vec<Term> {
+        let mut shapes = vec![];
+        let mut seen = HashSet::new();
+        let rdf_type = Term::Iri(RDF_TYPE.into());
+        let class = Term::Iri(RDFS_CLASS.into());
+        for target in TARGETS {
+            for triple in self.shapes.matching(None, Some(&sh(target)), None) {
+                if seen.insert(triple.subject.clone()) {
+                    shapes.push(triple.subject.clone());
+                }
+            }
+        }
+        for triple in self.shapes.matching(None, Some(&rdf_type), Some(&class)) {
+            let is_shape = ["NodeShape", "PropertyShape"].into_iter().any(|kind| {
+                self.shapes
+                    .matching(Some(&triple.subject), Some(&rdf_type), Some(&sh(kind)))
+                    .next()
+                    .is_some()
+            });
+            if is_shape && seen.insert(triple.subject.clone()) {
+                shapes.push(triple.subject.clone());
+            }
+        }
+        shapes
+    }
+
+    fn focus_nodes(&self, shape: &Term) -> Vec<Term> {
+        let mut nodes = vec![];
+        let mut classes = self.objects(shape, "targetClass");
+        if self
+            .shapes
+            .matching(
+                Some(shape),
+                Some(&Term::Iri(RDF_TYPE.into())),
+                Some(&Term::Iri(RDFS_CLASS.into())),
+            )
+            .next()
+            .is_some()
+        {
+            classes.push(shape.clone());
+        }
+        let instances = instances_path();
+        let evaluator = PathEvaluator::new(self.data, false);
+        for class in classes {
+            nodes.extend(evaluator.eval(&instances, &class).nodes);
+        }
+        nodes.extend(self.objects(shape, "targetNode"));
+        for predicate in self.objects(shape, "targetSubjectsOf") {
+            nodes.extend(
+                self.data
+                    .matching(None, Some(&predicate), None)
+                    .map(|t| t.subject.clone()),
+            );
+        }
+        for predicate in self.objects(shape, "targetObjectsOf") {
+            nodes.extend(
+                self.data
+                    .matching(None, Some(&predicate), None)
+                    .map(|t| t.object.clone()),
+            );
+        }
+        let mut seen = HashSet::new();
+        nodes.retain(|node| seen.insert(node.clone()));
+        nodes
+    }
+
+    fn is_instance_of(&self, node: &Term, class: &Term) -> bool {
+        let types = PropertyPath::Sequence(
+            Box::new(PropertyPath::Predicate(Term::Iri(RDF_TYPE.into()))),
+            Box::new(PropertyPath::ZeroOrMore(Box::new(PropertyPath::Predicate(
+                Term::Iri(RDFS_SUB_CLASS_OF.into()),
+            )))),
+        );
+        PathEvaluator::new(self.data, false)
+            .eval(&types, node)
+            .nodes
+            .contains(class)
+    }
+
+    fn conforms(&self, shape: &Term, node: &Term, depth: usize) -> Result<bool, TurtleDocError> {
+        let mut results = vec![];
+        self.validate_shape(shape, node, depth, &mut results)?;
+        Ok(results.is_empty())
+    }
+
+    fn validate_shape(
+        &self,
+        shape: &Term,
+        focus: &Term,
+        depth: usize,
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), TurtleDocError> {
+        if depth > MAX_SHAPE_DEPTH || self.object(shape, "deactivated") == Some(boolean(true)) {
+            return Ok(());
+        }
+        let path = self.object(shape, "path");
+        let values = match &path {
+            Some(path) => {
+                PathEvaluator::new(self.data, false)
+                    .eval(&self.path(path)?, focus)
+                    .nodes
+            }
+            None => vec![focus.clone()],
+        };
+        let severity = self
+            .object(shape, "severity")
+            .unwrap_or_else(|| sh("Violation"));
+        let message = self.object(shape, "message");
+        let mut fail = |component: &'static str, value: Option<&Term>| {
+            results.push(ValidationResult {
+                focus: focus.clone(),
+                path: path.clone(),
+                value: value.cloned(),
+                shape: shape.clone(),
+                component,
+                severity: severity.clone(),
+                message: message.clone(),
+            })
+        };
+
+        let count = values.len() as f64;
+        for min in self.objects(shape, "minCount") {
+            if numeric_value(&min).map(|min| count < min).unwrap_or(false) {
+                fail("MinCountConstraintComponent", None);
+            }
+        }
+        for max in self.objects(shape, "maxCount") {
+            if numeric_value(&max).map(|max| count > max).unwrap_or(false) {
+                fail("MaxCountConstraintComponent", None);
+            }
+        }
+        let classes = self.objects(shape, "class");
+        let datatypes = self.objects(shape, "datatype");
+        let node_kinds = self.objects(shape, "nodeKind");
+        let flags = match self.object(shape, "flags") {
+            Some(Term::Literal { value, .. }) => value,
+            _ => String::new(),
+        };
+        let mut patterns = vec![];
+        for pattern in self.objects(shape, "pattern") {
+            if let Term::Literal { value, .. } = pattern {
+                patterns.push(compile_regex(&value, &flags).map_err(|e| TurtleDocError {
+                    message: format!("invalid sh:pattern {value}: {e}"),
+                })?);
+            }
+        }
+        let ins: Vec<Vec<Term>> = self
+            .objects(shape, "in")
+            .into_iter()
+            .map(|list| self.list(list))
+            .collect();
+        let nodes = self.objects(shape, "node");
+        let ranges: Vec<(Term, &'static str, &[Ordering])> = RANGES
+            .iter()
+            .flat_map(|(key, component, violations)| {
+                self.objects(shape, key)
+                    .into_iter()
+                    .map(|bound| (bound, *component, *violations))
+            })
+            .collect();
+
+        for value in &values {
+            for class in &classes {
+                if !self.is_instance_of(value, class) {
+                    fail("ClassConstraintComponent", Some(value));
+                }
+            }
+            for datatype in &datatypes {
+                if datatype_of(value).as_ref() != Some(datatype) {
+                    fail("DatatypeConstraintComponent", Some(value));
+                }
+            }
+            for node_kind in &node_kinds {
+                if !has_node_kind(value, node_kind) {
+                    fail("NodeKindConstraintComponent", Some(value));
+                }
+            }
+            for pattern in &patterns {
+                let matches = match value {
+                    Term::Iri(iri) => pattern.is_match(iri),
+                    Term::Literal { value, .. } => pattern.is_match(value),
+                    Term::BlankNode(_) => false,
+                };
+                if !matches {
+                    fail("PatternConstraintComponent", Some(value));
+                }
+            }
+            for members in &ins {
+                if !members.contains(value) {
+                    fail("InConstraintComponent", Some(value));
+                }
+            }
+            for (bound, component, violations) in &ranges {
+                let violated = compare(value, bound)
+                    .map(|ordering| violations.contains(&ordering))
+                    .unwrap_or(true);
+                if violated {
+                    fail(component, Some(value));
+                }
+            }
+            for node in &nodes {
+                if !self.conforms(node, value, depth + 1)? {
+                    fail("NodeConstraintComponent", Some(value));
+                }
+            }
+        }
+
+        for property in self.objects(shape, "property") {
+            self.validate_shape(&property, focus, depth, results)?;
+        }
+        Ok(())
+    }
+
+    /// the triples of a path of the shapes graph, following its blank nodes
+    fn path_triples(&self, path: &Term, triples: &mut Vec<Triple>, visited: &mut HashSet<Term>) {
+        if !matches!(path, Term::BlankNode(_)) || !visited.insert(path.clone()) {
+            return;
+        }
+        for triple in self.shapes.matching(Some(path), None, None) {
+            triples.push(triple.clone());
+            self.path_triples(&triple.object, triples, visited);
+        }
+    }
+
+    fn report(&self, results: Vec<ValidationResult>) -> Vec<Triple> {
+        let report = Term::BlankNode("report".into());
+        let mut triples = vec![];
+        let mut add = |subject: &Term, predicate: Term, object: Term| {
+            triples.push(Triple {
+                subject: subject.clone(),
+                predicate,
+                object,
+            })
+        };
+        add(&report, Term::Iri(RDF_TYPE.into()), sh("ValidationReport"));
+        add(&report, sh("conforms"), boolean(results.is_empty()));
+        let mut paths = vec![];
+        let mut visited = HashSet::new();
+        for (idx, result) in results.into_iter().enumerate() {
+            let node = Term::BlankNode(format!("result{idx}"));
+            add(&report, sh("result"), node.clone());
+            add(&node, Term::Iri(RDF_TYPE.into()), sh("ValidationResult"));
+            add(&node, sh("focusNode"), result.focus);
+            if let Some(path) = result.path {
+                self.path_triples(&path, &mut paths, &mut visited);
+                add(&node, sh("resultPath"), path);
+            }
+            if let Some(value) = result.value {
+                add(&node, sh("value"), value);
+            }
+            add(&node, sh("sourceShape"), result.shape);
+            add(&node, sh("sourceConstraintComponent"), sh(result.component));
+            add(&node, sh("resultSeverity"), result.severity);
+            if let Some(message) = result.message {
+                add(&node, sh("resultMessage"), message);
+            }
+        }
+        triples.extend(paths);
+        triples
+    }
+}
+
+/// ^rdf:type or the instances of the subclasses, i.e (^rdfs:subClassOf)*/^rdf:type
+fn instances_path() -> PropertyPath {
+    PropertyPath::Sequence(
+        Box::new(PropertyPath::ZeroOrMore(Box::new(PropertyPath::Inverse(
+            Box::new(PropertyPath::Predicate(Term::Iri(RDFS_SUB_CLASS_OF.into()))),
+        )))),
+        Box::new(PropertyPath::Inverse(Box::new(PropertyPath::Predicate(
+            Term::Iri(RDF_TYPE.into()),
+        )))),
+    )
+}
+
+fn boolean(value: bool) -> Term {
+    Term::Literal {
+        value: value.to_string(),
+        datatype: Some(XSD_BOOLEAN.into()),
+        lang: None,
+    }
+}
+
+fn datatype_of(term: &Term) -> Option<Term> {
+    match term {
+        Term::Literal { lang: Some(_), .. } => Some(Term::Iri(RDF_LANG_STRING.into())),
+        Term::Literal { datatype, .. } => Some(Term::Iri(
+            datatype.clone().unwrap_or_else(|| XSD_STRING.into()),
+        )),
+        _ => None,
+    }
+}
+
+fn has_node_kind(term: &Term, node_kind: &Term) -> bool {
+    let Term::Iri(node_kind) = node_kind else {
+        return false;
+    };
+    let kinds: &[&str] = match term {
+        Term::Iri(_) => &["IRI", "BlankNodeOrIRI", "IRIOrLiteral"],
+        Term::BlankNode(_) => &["BlankNode", "BlankNodeOrIRI", "BlankNodeOrLiteral"],
+        Term::Literal { .. } => &["Literal", "BlankNodeOrLiteral", "IRIOrLiteral"],
+    };
+    node_kind
+        .strip_prefix(SH)
+        .map(|kind| kinds.contains(&kind))
+        .unwrap_or(false)
+}
+
+/// numbers by value, other literals of the same datatype by lexical form,
+/// None when the terms are not comparable
+fn compare(value: &Term, bound: &Term) -> Option<Ordering> {
+    if let (Some(value), Some(bound)) = (numeric_value(value), numeric_value(bound)) {
+        return value.partial_cmp(&bound);
+    }
+    match (value, bound) {
+        (
+            Term::Literal {
+                value,
+                datatype,
+                lang: None,
+            },
+            Term::Literal {
+                value: bound,
+                datatype: bound_datatype,
+                lang: None,
+            },
+        ) if datatype == bound_datatype => Some(value.cmp(bound)),
+        _ => None,
+    }
+}
+
+pub fn validate(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let mut prefixes = BTreeMap::new();
+    let data = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => Graph::new(triples),
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let shapes = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_RHS_PATH,
+        PARAMS_RHS_DATA,
+    ) {
+        Ok(triples) => Graph::new(triples),
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let validator = Validator {
+        data: &data,
+        shapes: &shapes,
+    };
+    let mut results = vec![];
+    for shape in validator.shapes_with_targets() {
+        for focus in validator.focus_nodes(&shape) {
+            if let Err(e) = validator.validate_shape(&shape, &focus, 0, &mut results) {
+                return cx.throw_error(e.message);
+            }
+        }
+    }
+    make_triples_response(&params, &mut cx, &validator.report(results), &prefixes)
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use neon::prelude::*;
+
+use crate::{
+    graph::{Term, Triple},
+    obj::{load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+};
+
+const PARAMS_SKOLEM_BASE: &str = "skolemBase";
+
+const WELL_KNOWN_GENID: &str = "/.well-known/genid/";
+
+pub fn skolemize(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let base: String = match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_SKOLEM_BASE)? {
+        Some(base) => base.value(&mut cx),
+        None => return cx.throw_error(format!("missing base ('{PARAMS_SKOLEM_BASE}')")),
+    };
+    let prefix = format!("{}{WELL_KNOWN_GENID}", base.trim_end_matches('/'));
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let skolemize_term = |term: Term| match term {
+        Term::BlankNode(label) => Term::Iri(format!("{prefix}{label}")),
+        term => term,
+    };
+    let triples: Vec<Triple> = triples
+        .into_iter()
+        .map(|t| Triple {
+            subject: skolemize_term(t.subject),
+            predicate: t.predicate,
+            object: skolemize_term(t.object),
+        })
+        .collect();
+    make_triples_response(&params, &mut cx, &triples, &prefixes)
+}
+
+pub fn deskolemize(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    // without a base, every iri with a well known genid path is deskolemized
+    let prefix: Option<String> = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_SKOLEM_BASE)?
+        .map(|base| {
+            format!(
+                "{}{WELL_KNOWN_GENID}",
+                base.value(&mut cx).trim_end_matches('/')
+            )
+        });
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let label = |iri: &str| -> Option<String> {
+        let label = match &prefix {
+            Some(prefix) => iri.strip_prefix(prefix.as_str())?,
+            None => iri.split_once(WELL_KNOWN_GENID)?.1,
+        };
+        (!label.is_empty()).then(|| label.to_string())
+    };
+    let deskolemize_term = |term: Term| match term {
+        Term::Iri(iri) => match label(&iri) {
+            Some(label) => Term::BlankNode(label),
+            None => Term::Iri(iri),
+        },
+        term => term,
+    };
+    let triples: Vec<Triple> = triples
+        .into_iter()
+        .map(|t| Triple {
+            subject: deskolemize_term(t.subject),
+            predicate: t.predicate,
+            object: deskolemize_term(t.object),
+        })
+        .collect();
+    make_triples_response(&params, &mut cx, &triples, &prefixes)
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use neon::prelude::*;
+
+use crate::{
+    graph::{Term, Triple},
+    obj::{load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+};
+
+const PARAMS_PREFERRED_NAMESPACES: &str = "preferredNamespaces";
+const PARAMS_KEEP_SAME_AS: &str = "keepSameAs";
+
+const OWL_SAME_AS: &str = "http://www.w3.org/2002/07/owl#sameAs";
+
+/// equivalence classes of owl:sameAs
+#[derive(Default)]
+struct SameAs {
+    parents: HashMap<Term, Term>,
+}
+
+impl SameAs {
+    fn find(&mut self, term: &Term) -> Term {
+        let Some(parent) = self.parents.get(term).cloned() else {
+            return term.clone();
+        };
+        let root = self.find(&parent);
+        self.parents.insert(term.clone(), root.clone());
+        root
+    }
+
+    fn union(&mut self, a: &Term, b: &Term) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents.insert(a, b);
+        }
+    }
+
+    fn classes(mut self) -> Vec<BTreeSet<Term>> {
+        let terms: Vec<Term> = self.parents.keys().cloned().collect();
+        let mut classes: BTreeMap<Term, BTreeSet<Term>> = BTreeMap::new();
+        for term in terms {
+            let root = self.find(&term);
+            classes
+                .entry(root.clone())
+                .or_default()
+                .extend([term, root]);
+        }
+        classes.into_values().collect()
+    }
+}
+
+/// the iri of the first preferred namespace, then the smallest iri. a blank
+/// node is only canonical when the class has no iri
+fn canonical<'a>(class: &'a BTreeSet<Term>, preferred_namespaces: &[String]) -> Option<&'a Term> {
+    let iris = || {
+        class.iter().filter_map(|t| match t {
+            Term::Iri(iri) => Some((t, iri)),
+            _ => None,
+        })
+    };
+    preferred_namespaces
+        .iter()
+        .find_map(|ns| iris().find(|(_, iri)| iri.starts_with(ns.as_str())))
+        .or_else(|| iris().next())
+        .map(|(t, _)| t)
+        .or_else(|| class.first())
+}
+
+pub fn smush(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let preferred_namespaces: Vec<String> =
+        match params.get_opt::<JsValue, _, _>(&mut cx, PARAMS_PREFERRED_NAMESPACES)? {
+            None => vec![],
+            Some(value) => {
+                if let Ok(value) = value.downcast::<JsString, _>(&mut cx) {
+                    vec![value.value(&mut cx)]
+                } else if let Ok(values) = value.downcast::<JsArray, _>(&mut cx) {
+                    let mut namespaces = vec![];
+                    for value in values.to_vec(&mut cx)? {
+                        namespaces.push(
+                            value
+                                .downcast_or_throw::<JsString, _>(&mut cx)?
+                                .value(&mut cx),
+                        );
+                    }
+                    namespaces
+                } else {
+                    return cx.throw_error(format!(
+                        "'{PARAMS_PREFERRED_NAMESPACES}' must be a string or an array"
+                    ));
+                }
+            }
+        };
+    let keep_same_as = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_KEEP_SAME_AS)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+
+    let same_as = Term::Iri(OWL_SAME_AS.into());
+    let is_same_as =
+        |t: &Triple| t.predicate == same_as && !matches!(t.object, Term::Literal { .. });
+    let mut classes = SameAs::default();
+    for triple in triples.iter().filter(|t| is_same_as(t)) {
+        classes.union(&triple.subject, &triple.object);
+    }
+    let mut canonicals: HashMap<Term, Term> = HashMap::new();
+    let mut links = vec![];
+    for class in classes.classes() {
+        let Some(canonical) = canonical(&class, &preferred_namespaces) else {
+            continue;
+        };
+        for term in class.iter().filter(|t| *t != canonical) {
+            canonicals.insert(term.clone(), canonical.clone());
+            links.push(Triple {
+                subject: canonical.clone(),
+                predicate: same_as.clone(),
+                object: term.clone(),
+            });
+        }
+    }
+
+    let rewrite = |term: Term| canonicals.get(&term).cloned().unwrap_or(term);
+    let mut seen = HashSet::new();
+    let mut model: Vec<Triple> = triples
+        .into_iter()
+        .filter(|t| !is_same_as(t))
+        .map(|t| Triple {
+            subject: rewrite(t.subject),
+            predicate: rewrite(t.predicate),
+            object: rewrite(t.object),
+        })
+        .filter(|t| seen.insert(t.clone()))
+        .collect();
+    if keep_same_as {
+        model.extend(links);
+    }
+    make_triples_response(&params, &mut cx, &model, &prefixes)
+}
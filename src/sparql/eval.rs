@@ -0,0 +1,857 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use regex::{Regex, RegexBuilder};
+
+use super::parser::{
+    ArithmeticOp, CompareOp, Expression, Function, GraphPattern, OrderCondition, Query, QueryForm,
+    TermPattern, TriplePattern,
+};
+use crate::{
+    graph::{
+        lang_matches, Graph, Term, Triple, RDF_LANG_STRING, XSD_BOOLEAN, XSD_DECIMAL, XSD_DOUBLE,
+        XSD_INTEGER, XSD_STRING,
+    },
+    literals::{date_instant, date_time_instant},
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const XSD_DATE_TIME: &str = "http://www.w3.org/2001/XMLSchema#dateTime";
+const XSD_DATE: &str = "http://www.w3.org/2001/XMLSchema#date";
+
+const INTEGER_TYPES: [&str; 13] = [
+    "integer",
+    "int",
+    "long",
+    "short",
+    "byte",
+    "nonNegativeInteger",
+    "positiveInteger",
+    "negativeInteger",
+    "nonPositiveInteger",
+    "unsignedInt",
+    "unsignedLong",
+    "unsignedShort",
+    "unsignedByte",
+];
+
+pub type Solution = HashMap<String, Term>;
+
+pub enum QueryResult {
+    Solutions {
+        variables: Vec<String>,
+        solutions: Vec<Vec<Option<Term>>>,
+    },
+    Boolean(bool),
+    Graph(Vec<Triple>),
+}
+
+pub struct Evaluator<'a> {
+    graph: &'a Graph,
+    regexes: RefCell<HashMap<(String, String), Option<Regex>>>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(graph: &'a Graph) -> Evaluator<'a> {
+        Evaluator {
+            graph,
+            regexes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn query(&self, query: &Query) -> QueryResult {
+        let mut solutions = self.eval(&query.pattern, vec![Solution::new()]);
+        if let QueryForm::Ask = query.form {
+            return QueryResult::Boolean(!solutions.is_empty());
+        }
+        if !query.order_by.is_empty() {
+            let mut keyed: Vec<(Vec<Option<Term>>, Solution)> = solutions
+                .into_iter()
+                .map(|solution| {
+                    let keys = query
+                        .order_by
+                        .iter()
+                        .map(|condition| match condition {
+                            OrderCondition::Asc(e) | OrderCondition::Desc(e) => {
+                                self.eval_expression(e, &solution)
+                            }
+                        })
+                        .collect();
+                    (keys, solution)
+                })
+                .collect();
+            keyed.sort_by(|(lhs, _), (rhs, _)| {
+                for (idx, condition) in query.order_by.iter().enumerate() {
+                    let ordering = order_terms(lhs[idx].as_ref(), rhs[idx].as_ref());
+                    let ordering = match condition {
+                        OrderCondition::Asc(_) => ordering,
+                        OrderCondition::Desc(_) => ordering.reverse(),
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            });
+            solutions = keyed.into_iter().map(|(_, s)| s).collect();
+        }
+        match &query.form {
+            QueryForm::Select {
+                distinct,
+                variables,
+            } => {
+                let variables = variables
+                    .clone()
+                    .unwrap_or_else(|| in_scope_variables(&query.pattern));
+                let mut rows: Vec<Vec<Option<Term>>> = solutions
+                    .into_iter()
+                    .map(|s| variables.iter().map(|v| s.get(v).cloned()).collect())
+                    .collect();
+                if *distinct {
+                    let mut seen = HashSet::new();
+                    rows.retain(|row| seen.insert(row.clone()));
+                }
+                let rows = slice(rows, query.offset, query.limit);
+                QueryResult::Solutions {
+                    variables,
+                    solutions: rows,
+                }
+            }
+            QueryForm::Construct(template) => {
+                let solutions = slice(solutions, query.offset, query.limit);
+                let mut graph = Graph::default();
+                for (row, solution) in solutions.iter().enumerate() {
+                    for pattern in template {
+                        let fresh = |label: &str| format!("{label}_{row}");
+                        if let Some(triple) = instantiate(pattern, solution, fresh) {
+                            graph.insert(triple);
+                        }
+                    }
+                }
+                QueryResult::Graph(graph.into_triples())
+            }
+            QueryForm::Ask => unreachable!(),
+        }
+    }
+
+    /// substitution based evaluation, every input solution is extended
+    pub fn eval(&self, pattern: &GraphPattern, input: Vec<Solution>) -> Vec<Solution> {
+        match pattern {
+            GraphPattern::Bgp(patterns) => {
+                let mut out = vec![];
+                for solution in input {
+                    let mut remaining: Vec<&TriplePattern> = patterns.iter().collect();
+                    self.eval_bgp(&mut remaining, solution, &mut out);
+                }
+                out
+            }
+            GraphPattern::Join(lhs, rhs) => {
+                let solutions = self.eval(lhs, input);
+                self.eval(rhs, solutions)
+            }
+            GraphPattern::LeftJoin(lhs, rhs, expression) => {
+                let mut out = vec![];
+                for solution in self.eval(lhs, input) {
+                    let extended: Vec<Solution> = self
+                        .eval(rhs, vec![solution.clone()])
+                        .into_iter()
+                        .filter(|s| match expression {
+                            Some(e) => self.effective_boolean_value(e, s),
+                            None => true,
+                        })
+                        .collect();
+                    if extended.is_empty() {
+                        out.push(solution);
+                    } else {
+                        out.extend(extended);
+                    }
+                }
+                out
+            }
+            GraphPattern::Union(lhs, rhs) => {
+                let mut out = self.eval(lhs, input.clone());
+                out.extend(self.eval(rhs, input));
+                out
+            }
+            GraphPattern::Filter(expression, inner) => self
+                .eval(inner, input)
+                .into_iter()
+                .filter(|s| self.effective_boolean_value(expression, s))
+                .collect(),
+        }
+    }
+
+    /// matches the pattern with the most bound positions first
+    fn eval_bgp(
+        &self,
+        remaining: &mut Vec<&TriplePattern>,
+        solution: Solution,
+        out: &mut Vec<Solution>,
+    ) {
+        let Some((idx, _)) = remaining
+            .iter()
+            .enumerate()
+            .max_by_key(|(idx, p)| (bound_count(p, &solution), std::cmp::Reverse(*idx)))
+        else {
+            out.push(solution);
+            return;
+        };
+        let pattern = remaining.remove(idx);
+        let subject = resolve(&pattern.subject, &solution);
+        let predicate = resolve(&pattern.predicate, &solution);
+        let object = resolve(&pattern.object, &solution);
+        for triple in self.graph.matching(subject, predicate, object) {
+            let mut extended = solution.clone();
+            if bind(&pattern.subject, &triple.subject, &mut extended)
+                && bind(&pattern.predicate, &triple.predicate, &mut extended)
+                && bind(&pattern.object, &triple.object, &mut extended)
+            {
+                self.eval_bgp(remaining, extended, out);
+            }
+        }
+        remaining.insert(idx, pattern);
+    }
+
+    pub fn effective_boolean_value(&self, expression: &Expression, solution: &Solution) -> bool {
+        self.eval_expression(expression, solution)
+            .and_then(|t| ebv(&t))
+            .unwrap_or(false)
+    }
+
+    /// None is an evaluation error, e.g an unbound variable
+    pub fn eval_expression(&self, expression: &Expression, solution: &Solution) -> Option<Term> {
+        match expression {
+            Expression::Constant(term) => Some(term.clone()),
+            Expression::Variable(v) => solution.get(v).cloned(),
+            Expression::Bound(v) => Some(boolean(solution.contains_key(v))),
+            Expression::Or(lhs, rhs) => {
+                let lhs = self.eval_expression(lhs, solution).and_then(|t| ebv(&t));
+                let rhs = self.eval_expression(rhs, solution).and_then(|t| ebv(&t));
+                match (lhs, rhs) {
+                    (Some(true), _) | (_, Some(true)) => Some(boolean(true)),
+                    (Some(false), Some(false)) => Some(boolean(false)),
+                    _ => None,
+                }
+            }
+            Expression::And(lhs, rhs) => {
+                let lhs = self.eval_expression(lhs, solution).and_then(|t| ebv(&t));
+                let rhs = self.eval_expression(rhs, solution).and_then(|t| ebv(&t));
+                match (lhs, rhs) {
+                    (Some(false), _) | (_, Some(false)) => Some(boolean(false)),
+                    (Some(true), Some(true)) => Some(boolean(true)),
+                    _ => None,
+                }
+            }
+            Expression::Not(e) => {
+                let value = ebv(&self.eval_expression(e, solution)?)?;
+                Some(boolean(!value))
+            }
+            Expression::Compare(op, lhs, rhs) => {
+                let lhs = self.eval_expression(lhs, solution)?;
+                let rhs = self.eval_expression(rhs, solution)?;
+                compare(*op, &lhs, &rhs).map(boolean)
+            }
+            Expression::In(e, list, negated) => {
+                let value = self.eval_expression(e, solution)?;
+                let mut error = false;
+                for candidate in list {
+                    match self
+                        .eval_expression(candidate, solution)
+                        .and_then(|c| compare(CompareOp::Equal, &value, &c))
+                    {
+                        Some(true) => return Some(boolean(!negated)),
+                        Some(false) => {}
+                        None => error = true,
+                    }
+                }
+                if error {
+                    None
+                } else {
+                    Some(boolean(*negated))
+                }
+            }
+            Expression::Arithmetic(op, lhs, rhs) => {
+                let lhs = self.eval_expression(lhs, solution)?;
+                let rhs = self.eval_expression(rhs, solution)?;
+                arithmetic(*op, &lhs, &rhs)
+            }
+            Expression::Negate(e) => {
+                let value = self.eval_expression(e, solution)?;
+                arithmetic(
+                    ArithmeticOp::Subtract,
+                    &typed_literal("0".into(), XSD_INTEGER),
+                    &value,
+                )
+            }
+            Expression::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| self.eval_expression(a, solution))
+                    .collect::<Vec<_>>();
+                self.call(*function, args)
+            }
+        }
+    }
+
+    fn call(&self, function: Function, args: Vec<Option<Term>>) -> Option<Term> {
+        let arg = |idx: usize| args.get(idx).cloned().flatten();
+        match function {
+            Function::Str => match arg(0)? {
+                Term::Iri(iri) => Some(typed_literal(iri, XSD_STRING)),
+                Term::Literal { value, .. } => Some(typed_literal(value, XSD_STRING)),
+                Term::BlankNode(_) => None,
+            },
+            Function::Lang => match arg(0)? {
+                Term::Literal { lang, .. } => {
+                    Some(typed_literal(lang.unwrap_or_default(), XSD_STRING))
+                }
+                _ => None,
+            },
+            Function::Datatype => match arg(0)? {
+                Term::Literal { lang: Some(_), .. } => Some(Term::Iri(RDF_LANG_STRING.into())),
+                Term::Literal { datatype, .. } => {
+                    Some(Term::Iri(datatype.unwrap_or_else(|| XSD_STRING.into())))
+                }
+                _ => None,
+            },
+            Function::LangMatches => {
+                let tag = string_value(&arg(0)?)?;
+                let range = string_value(&arg(1)?)?;
+                Some(boolean(lang_matches(&tag, &range)))
+            }
+            Function::Regex => {
+                let text = string_value(&arg(0)?)?;
+                let pattern = simple_string(&arg(1)?)?;
+                let flags = match args.get(2) {
+                    Some(flags) => simple_string(flags.as_ref()?)?,
+                    None => String::new(),
+                };
+                let matched = self.regex(pattern, flags)?.is_match(&text);
+                Some(boolean(matched))
+            }
+            Function::IsIri => Some(boolean(matches!(arg(0)?, Term::Iri(_)))),
+            Function::IsBlank => Some(boolean(matches!(arg(0)?, Term::BlankNode(_)))),
+            Function::IsLiteral => Some(boolean(matches!(arg(0)?, Term::Literal { .. }))),
+            Function::IsNumeric => Some(boolean(numeric(&arg(0)?).is_some())),
+            Function::SameTerm => Some(boolean(arg(0)? == arg(1)?)),
+            Function::Contains | Function::StrStarts | Function::StrEnds => {
+                let lhs = arg(0)?;
+                let rhs = arg(1)?;
+                if !compatible_strings(&lhs, &rhs) {
+                    return None;
+                }
+                let lhs = string_value(&lhs)?;
+                let rhs = string_value(&rhs)?;
+                let result = match function {
+                    Function::Contains => lhs.contains(&rhs),
+                    Function::StrStarts => lhs.starts_with(&rhs),
+                    _ => lhs.ends_with(&rhs),
+                };
+                Some(boolean(result))
+            }
+            Function::LCase | Function::UCase => match arg(0)? {
+                Term::Literal {
+                    value,
+                    datatype,
+                    lang,
+                } if lang.is_some() || datatype.as_deref() == Some(XSD_STRING) => {
+                    Some(Term::Literal {
+                        value: if function == Function::LCase {
+                            value.to_lowercase()
+                        } else {
+                            value.to_uppercase()
+                        },
+                        datatype,
+                        lang,
+                    })
+                }
+                _ => None,
+            },
+            Function::StrLen => {
+                let value = string_value(&arg(0)?)?;
+                Some(typed_literal(
+                    value.chars().count().to_string(),
+                    XSD_INTEGER,
+                ))
+            }
+        }
+    }
+
+    fn regex(&self, pattern: String, flags: String) -> Option<Regex> {
+        self.regexes
+            .borrow_mut()
+            .entry((pattern, flags))
+            .or_insert_with_key(|(pattern, flags)| compile_regex(pattern, flags).ok())
+            .clone()
+    }
+}
+
+/// compiles a regex with the xpath flags (i, m, s, x)
+pub fn compile_regex(pattern: &str, flags: &str) -> Result<Regex, String> {
+    let mut builder = RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            'x' => builder.ignore_whitespace(true),
+            f => return Err(format!("unknown regex flag {f}")),
+        };
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn slice<T>(items: Vec<T>, offset: usize, limit: Option<usize>) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+fn bound_count(pattern: &TriplePattern, solution: &Solution) -> usize {
+    [&pattern.subject, &pattern.predicate, &pattern.object]
+        .into_iter()
+        .filter(|p| resolve(p, solution).is_some())
+        .count()
+}
+
+fn resolve<'a>(pattern: &'a TermPattern, solution: &'a Solution) -> Option<&'a Term> {
+    match pattern {
+        TermPattern::Term(term) => Some(term),
+        TermPattern::Variable(v) => solution.get(v),
+    }
+}
+
+/// binds the variable, or checks it is consistent with a previous binding
+fn bind(pattern: &TermPattern, term: &Term, solution: &mut Solution) -> bool {
+    match pattern {
+        TermPattern::Term(t) => t == term,
+        TermPattern::Variable(v) => match solution.get(v) {
+            Some(bound) => bound == term,
+            None => {
+                solution.insert(v.clone(), term.clone());
+                true
+            }
+        },
+    }
+}
+
+/// template triple for a solution, blank nodes of the template are
+/// relabeled with fresh. None if a variable is unbound or the triple is invalid
+pub fn instantiate(
+    pattern: &TriplePattern,
+    solution: &Solution,
+    fresh: impl Fn(&str) -> String,
+) -> Option<Triple> {
+    let term = |p: &TermPattern| match p {
+        TermPattern::Term(Term::BlankNode(label)) => Some(Term::BlankNode(fresh(label))),
+        TermPattern::Term(term) => Some(term.clone()),
+        TermPattern::Variable(v) => solution.get(v).cloned(),
+    };
+    let triple = Triple {
+        subject: term(&pattern.subject)?,
+        predicate: term(&pattern.predicate)?,
+        object: term(&pattern.object)?,
+    };
+    match (&triple.subject, &triple.predicate) {
+        (Term::Literal { .. }, _) | (_, Term::Literal { .. } | Term::BlankNode(_)) => None,
+        _ => Some(triple),
+    }
+}
+
+/// variables of the pattern in order of appearance, blank nodes excluded
+pub fn in_scope_variables(pattern: &GraphPattern) -> Vec<String> {
+    fn collect(pattern: &GraphPattern, out: &mut Vec<String>) {
+        match pattern {
+            GraphPattern::Bgp(patterns) => {
+                for p in patterns {
+                    for position in [&p.subject, &p.predicate, &p.object] {
+                        if let TermPattern::Variable(v) = position {
+                            if !v.starts_with("_:") && !out.contains(v) {
+                                out.push(v.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            GraphPattern::Join(lhs, rhs)
+            | GraphPattern::LeftJoin(lhs, rhs, _)
+            | GraphPattern::Union(lhs, rhs) => {
+                collect(lhs, out);
+                collect(rhs, out);
+            }
+            GraphPattern::Filter(_, inner) => collect(inner, out),
+        }
+    }
+    let mut out = vec![];
+    collect(pattern, &mut out);
+    out
+}
+
+fn boolean(value: bool) -> Term {
+    typed_literal(value.to_string(), XSD_BOOLEAN)
+}
+
+fn typed_literal(value: String, datatype: &str) -> Term {
+    Term::Literal {
+        value,
+        datatype: Some(datatype.to_string()),
+        lang: None,
+    }
+}
+
+fn datatype(term: &Term) -> Option<&str> {
+    match term {
+        Term::Literal {
+            datatype: Some(dt),
+            lang: None,
+            ..
+        } => Some(dt.as_str()),
+        Term::Literal { lang: None, .. } => Some(XSD_STRING),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum NumericKind {
+    Integer,
+    Decimal,
+    Double,
+}
+
+fn numeric(term: &Term) -> Option<(f64, NumericKind)> {
+    let Term::Literal { value, .. } = term else {
+        return None;
+    };
+    let local = datatype(term)?.strip_prefix(XSD)?;
+    let kind = match local {
+        "decimal" => NumericKind::Decimal,
+        "double" | "float" => NumericKind::Double,
+        l if INTEGER_TYPES.contains(&l) => NumericKind::Integer,
+        _ => return None,
+    };
+    let value = value.trim();
+    let parsed = match value {
+        "INF" | "+INF" => f64::INFINITY,
+        "-INF" => f64::NEG_INFINITY,
+        "NaN" => f64::NAN,
+        v => v.parse().ok()?,
+    };
+    Some((parsed, kind))
+}
+
+/// value of a numeric literal, None for any other term
+pub fn numeric_value(term: &Term) -> Option<f64> {
+    numeric(term).map(|(value, _)| value)
+}
+
+/// exact value of an integer or a decimal, mantissa × 10^-scale
+#[derive(Clone, Copy)]
+struct Exact {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Exact {
+    fn parse(value: &str) -> Option<Exact> {
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (integral, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let fraction = fraction.trim_end_matches('0');
+        let digits = format!("{integral}{fraction}");
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let mantissa: i128 = digits.parse().ok()?;
+        Some(Exact {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: fraction.len() as u32,
+        })
+    }
+
+    fn mantissa_at(self, scale: u32) -> Option<i128> {
+        self.mantissa
+            .checked_mul(10i128.checked_pow(scale - self.scale)?)
+    }
+
+    /// both mantissas at the same scale, None on overflow
+    fn aligned(self, other: Exact) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.mantissa_at(scale)?, other.mantissa_at(scale)?, scale))
+    }
+
+    fn compare(self, other: Exact) -> Option<Ordering> {
+        let (lhs, rhs, _) = self.aligned(other)?;
+        Some(lhs.cmp(&rhs))
+    }
+
+    fn apply(self, op: ArithmeticOp, other: Exact) -> Option<Exact> {
+        let (mantissa, scale) = match op {
+            ArithmeticOp::Add => {
+                let (lhs, rhs, scale) = self.aligned(other)?;
+                (lhs.checked_add(rhs)?, scale)
+            }
+            ArithmeticOp::Subtract => {
+                let (lhs, rhs, scale) = self.aligned(other)?;
+                (lhs.checked_sub(rhs)?, scale)
+            }
+            ArithmeticOp::Multiply => (
+                self.mantissa.checked_mul(other.mantissa)?,
+                self.scale.checked_add(other.scale)?,
+            ),
+            ArithmeticOp::Divide => return None,
+        };
+        Some(Exact { mantissa, scale })
+    }
+
+    fn to_term(self, kind: NumericKind) -> Term {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integral, fraction) = digits.split_at(digits.len() - scale);
+        match (kind, fraction.trim_end_matches('0')) {
+            (NumericKind::Integer, "") => typed_literal(format!("{sign}{integral}"), XSD_INTEGER),
+            (_, "") => typed_literal(format!("{sign}{integral}.0"), XSD_DECIMAL),
+            (_, fraction) => typed_literal(format!("{sign}{integral}.{fraction}"), XSD_DECIMAL),
+        }
+    }
+}
+
+/// exact value of an integer or decimal literal, None for doubles, floats
+/// and values too large for an i128
+fn exact(term: &Term) -> Option<Exact> {
+    match (term, numeric(term)?) {
+        (Term::Literal { value, .. }, (_, NumericKind::Integer | NumericKind::Decimal)) => {
+            Exact::parse(value.trim())
+        }
+        _ => None,
+    }
+}
+
+fn format_numeric(value: f64, kind: NumericKind) -> Term {
+    match kind {
+        NumericKind::Integer => typed_literal(format!("{}", value as i64), XSD_INTEGER),
+        NumericKind::Decimal => {
+            let mut formatted = format!("{value}");
+            if !formatted.contains('.') {
+                formatted.push_str(".0");
+            }
+            typed_literal(formatted, XSD_DECIMAL)
+        }
+        NumericKind::Double => typed_literal(format!("{value:E}"), XSD_DOUBLE),
+    }
+}
+
+fn arithmetic(op: ArithmeticOp, lhs: &Term, rhs: &Term) -> Option<Term> {
+    // integers and decimals are computed exactly, doubles and overflowing
+    // values fall back to f64
+    if let Some(value) = exact(lhs).zip(exact(rhs)).and_then(|(l, r)| l.apply(op, r)) {
+        let kind = match (numeric(lhs)?.1, numeric(rhs)?.1) {
+            (NumericKind::Integer, NumericKind::Integer) => NumericKind::Integer,
+            _ => NumericKind::Decimal,
+        };
+        return Some(value.to_term(kind));
+    }
+    let (lhs, lhs_kind) = numeric(lhs)?;
+    let (rhs, rhs_kind) = numeric(rhs)?;
+    let mut kind = if lhs_kind > rhs_kind {
+        lhs_kind
+    } else {
+        rhs_kind
+    };
+    let value = match op {
+        ArithmeticOp::Add => lhs + rhs,
+        ArithmeticOp::Subtract => lhs - rhs,
+        ArithmeticOp::Multiply => lhs * rhs,
+        ArithmeticOp::Divide => {
+            if kind == NumericKind::Integer {
+                kind = NumericKind::Decimal;
+            }
+            if rhs == 0.0 && kind == NumericKind::Decimal {
+                return None;
+            }
+            lhs / rhs
+        }
+    };
+    Some(format_numeric(value, kind))
+}
+
+/// strings, simple literals and language tagged literals
+fn string_value(term: &Term) -> Option<String> {
+    match term {
+        Term::Literal {
+            value,
+            lang: Some(_),
+            ..
+        } => Some(value.clone()),
+        Term::Literal { value, .. } if datatype(term) == Some(XSD_STRING) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn simple_string(term: &Term) -> Option<String> {
+    match term {
+        Term::Literal { value, .. } if datatype(term) == Some(XSD_STRING) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// argument compatibility rules of the sparql string functions
+fn compatible_strings(lhs: &Term, rhs: &Term) -> bool {
+    let lang = |t: &Term| match t {
+        Term::Literal { lang, .. } => lang.clone(),
+        _ => None,
+    };
+    string_value(lhs).is_some()
+        && string_value(rhs).is_some()
+        && (lang(rhs).is_none() || lang(lhs) == lang(rhs))
+}
+
+fn ebv(term: &Term) -> Option<bool> {
+    if let Some((value, _)) = numeric(term) {
+        return Some(value != 0.0 && !value.is_nan());
+    }
+    match (term, datatype(term)) {
+        (Term::Literal { value, .. }, Some(XSD_BOOLEAN)) => Some(value == "true" || value == "1"),
+        (Term::Literal { value, .. }, Some(XSD_STRING)) => Some(!value.is_empty()),
+        _ => None,
+    }
+}
+
+fn compare(op: CompareOp, lhs: &Term, rhs: &Term) -> Option<bool> {
+    let ordering = value_ordering(lhs, rhs);
+    match op {
+        CompareOp::Equal => match ordering {
+            Some(ordering) => Some(ordering == Ordering::Equal),
+            None if lhs == rhs => Some(true),
+            // two literals of an unknown type can't be proven different
+            None if matches!(
+                (lhs, rhs),
+                (
+                    Term::Literal { lang: None, .. },
+                    Term::Literal { lang: None, .. }
+                )
+            ) =>
+            {
+                None
+            }
+            None => Some(false),
+        },
+        CompareOp::NotEqual => compare(CompareOp::Equal, lhs, rhs).map(|equal| !equal),
+        CompareOp::Less => ordering.map(|o| o == Ordering::Less),
+        CompareOp::Greater => ordering.map(|o| o == Ordering::Greater),
+        CompareOp::LessOrEqual => ordering.map(|o| o != Ordering::Greater),
+        CompareOp::GreaterOrEqual => ordering.map(|o| o != Ordering::Less),
+    }
+}
+
+/// instant of a dateTime or date literal, see date_time_instant
+fn instant(term: &Term) -> Option<(i64, f64, bool)> {
+    let Term::Literal { value, .. } = term else {
+        return None;
+    };
+    match datatype(term)? {
+        XSD_DATE_TIME => date_time_instant(value),
+        XSD_DATE => date_instant(value),
+        _ => None,
+    }
+}
+
+/// ordering of two comparable values, None if not comparable
+fn value_ordering(lhs: &Term, rhs: &Term) -> Option<Ordering> {
+    if let Some(ordering) = exact(lhs).zip(exact(rhs)).and_then(|(l, r)| l.compare(r)) {
+        return Some(ordering);
+    }
+    if let (Some((lhs, _)), Some((rhs, _))) = (numeric(lhs), numeric(rhs)) {
+        return lhs.partial_cmp(&rhs);
+    }
+    match (lhs, rhs) {
+        (
+            Term::Literal {
+                value: lv,
+                lang: Some(ll),
+                ..
+            },
+            Term::Literal {
+                value: rv,
+                lang: Some(rl),
+                ..
+            },
+        ) if ll.eq_ignore_ascii_case(rl) => Some(lv.cmp(rv)),
+        (Term::Literal { value: lv, .. }, Term::Literal { value: rv, .. }) => {
+            match (datatype(lhs)?, datatype(rhs)?) {
+                (XSD_STRING, XSD_STRING) => Some(lv.cmp(rv)),
+                (XSD_BOOLEAN, XSD_BOOLEAN) => {
+                    Some((lv == "true" || lv == "1").cmp(&(rv == "true" || rv == "1")))
+                }
+                (XSD_DATE_TIME, XSD_DATE_TIME) | (XSD_DATE, XSD_DATE) => {
+                    let ((lm, ls, ltz), (rm, rs, rtz)) = (instant(lhs)?, instant(rhs)?);
+                    // a local time can't be compared to a timezoned one
+                    if ltz != rtz {
+                        return None;
+                    }
+                    Some(lm.cmp(&rm).then(ls.partial_cmp(&rs)?))
+                }
+                (l, r) if l == r && lv == rv => Some(Ordering::Equal),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// total order used by ORDER BY: unbound, blank nodes, iris, numeric
+/// literals, strings then any other literal. terms are ranked by class first
+/// and compared by value within a class, ties being broken by the term
+/// itself so that sorting never sees an inconsistent comparison.
+pub fn order_terms(lhs: Option<&Term>, rhs: Option<&Term>) -> Ordering {
+    let rank = |t: Option<&Term>| match t {
+        None => 0,
+        Some(Term::BlankNode(_)) => 1,
+        Some(Term::Iri(_)) => 2,
+        Some(t) if numeric(t).is_some() => 3,
+        Some(Term::Literal { lang: Some(_), .. }) => 4,
+        Some(t) if datatype(t) == Some(XSD_STRING) => 4,
+        Some(Term::Literal { .. }) => 5,
+    };
+    let (l, r) = match (lhs, rhs) {
+        (Some(l), Some(r)) if rank(lhs) == rank(rhs) => (l, r),
+        _ => return rank(lhs).cmp(&rank(rhs)),
+    };
+    let by_value = match rank(lhs) {
+        // values equal as f64 are then ordered exactly, doubles first
+        3 => match (numeric(l), numeric(r)) {
+            (Some((lv, _)), Some((rv, _))) => {
+                lv.total_cmp(&rv).then_with(|| match (exact(l), exact(r)) {
+                    (Some(le), Some(re)) => le.compare(re).unwrap_or(Ordering::Equal),
+                    (None, Some(_)) => Ordering::Less,
+                    (Some(_), None) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                })
+            }
+            _ => Ordering::Equal,
+        },
+        4 => match (l, r) {
+            (Term::Literal { value: lv, .. }, Term::Literal { value: rv, .. }) => lv.cmp(rv),
+            _ => Ordering::Equal,
+        },
+        5 => datatype(l)
+            .cmp(&datatype(r))
+            .then_with(|| match (instant(l), instant(r)) {
+                // local times are ordered as if they were in UTC
+                (Some((lm, ls, _)), Some((rm, rs, _))) => lm.cmp(&rm).then(ls.total_cmp(&rs)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => value_ordering(l, r).unwrap_or(Ordering::Equal),
+            }),
+        _ => Ordering::Equal,
+    };
+    by_value.then_with(|| l.cmp(r))
+}
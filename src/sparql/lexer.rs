@@ -0,0 +1,281 @@
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Iri(String),
+    PrefixedName(String, String),
+    Variable(String),
+    BlankNode(String),
+    String(String),
+    LangTag(String),
+    Integer(String),
+    Decimal(String),
+    Double(String),
+    Keyword(String),
+    Punct(&'static str),
+}
+
+const PUNCTS: [&str; 27] = [
+    "^^", "=>", "!=", "<=", ">=", "&&", "||", "{", "}", "(", ")", "[", "]", ".", ",", ";", "*",
+    "=", "<", ">", "!", "+", "-", "/", "^", "|", "?",
+];
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn tokenize(input: &'a str) -> Result<Vec<(Token, usize)>, TurtleDocError> {
+        let mut lexer = Lexer { input, pos: 0 };
+        let mut tokens = vec![];
+        while let Some(token) = lexer.next_token()? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, message: impl Into<String>) -> TurtleDocError {
+        TurtleDocError {
+            message: format!("{} at position {}", message.into(), self.pos),
+        }
+    }
+
+    fn skip_ws_and_comments(&mut self) {
+        loop {
+            let trimmed = self.rest().trim_start();
+            self.pos = self.input.len() - trimmed.len();
+            if trimmed.starts_with('#') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn next_token(&mut self) -> Result<Option<(Token, usize)>, TurtleDocError> {
+        self.skip_ws_and_comments();
+        let start = self.pos;
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+        let rest = self.rest();
+        let token = match c {
+            '<' => {
+                if let Some(iri) = self.read_iri() {
+                    Token::Iri(iri)
+                } else {
+                    self.read_punct()?
+                }
+            }
+            '?' | '$' if rest[1..].starts_with(is_var_char) => {
+                self.pos += 1;
+                Token::Variable(self.take_while(is_var_char).to_string())
+            }
+            '"' | '\'' => Token::String(self.read_string()?),
+            '@' => {
+                self.pos += 1;
+                let tag = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-');
+                if tag.is_empty() {
+                    return Err(self.error("empty language tag"));
+                }
+                Token::LangTag(tag.to_string())
+            }
+            '_' if rest.starts_with("_:") => {
+                self.pos += 2;
+                Token::BlankNode(self.read_local_name())
+            }
+            c if c.is_ascii_digit() => self.read_number(),
+            '.' if rest[1..].starts_with(|c: char| c.is_ascii_digit()) => self.read_number(),
+            ':' => {
+                self.pos += 1;
+                Token::PrefixedName(String::new(), self.read_local_name())
+            }
+            c if is_name_start_char(c) => {
+                let name = self.take_while(|c| is_name_char(c) || c == '.');
+                // a prefix can contain dots but not end with one
+                let trimmed = name.trim_end_matches('.');
+                self.pos -= name.len() - trimmed.len();
+                if self.rest().starts_with(':') {
+                    self.pos += 1;
+                    Token::PrefixedName(trimmed.to_string(), self.read_local_name())
+                } else {
+                    let keyword = trimmed.trim_end_matches('-');
+                    self.pos -= trimmed.len() - keyword.len();
+                    Token::Keyword(keyword.to_string())
+                }
+            }
+            _ => self.read_punct()?,
+        };
+        Ok(Some((token, start)))
+    }
+
+    fn read_punct(&mut self) -> Result<Token, TurtleDocError> {
+        let rest = self.rest();
+        let punct = PUNCTS
+            .iter()
+            .find(|p| rest.starts_with(**p))
+            .ok_or_else(|| self.error(format!("unexpected character {:?}", self.peek())))?;
+        self.pos += punct.len();
+        Ok(Token::Punct(punct))
+    }
+
+    fn read_iri(&mut self) -> Option<String> {
+        let rest = &self.rest()[1..];
+        let end = rest.find(|c: char| {
+            c == '>' || c.is_whitespace() || matches!(c, '<' | '"' | '{' | '}' | '|' | '^' | '`')
+        })?;
+        if !rest[end..].starts_with('>') {
+            return None;
+        }
+        self.pos += end + 2;
+        Some(unescape(&rest[..end]))
+    }
+
+    fn read_local_name(&mut self) -> String {
+        let rest = self.rest();
+        let mut name = String::new();
+        let mut end = 0;
+        let mut chars = rest.char_indices().peekable();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((idx, escaped)) => {
+                        name.push(escaped);
+                        end = idx + escaped.len_utf8();
+                    }
+                    None => break,
+                },
+                c if is_name_char(c) || matches!(c, '.' | ':' | '%') => {
+                    name.push(c);
+                    end = idx + c.len_utf8();
+                }
+                _ => break,
+            }
+        }
+        // the final dot ends the triple
+        while name.ends_with('.') {
+            name.pop();
+            end -= 1;
+        }
+        self.pos += end;
+        name
+    }
+
+    fn read_number(&mut self) -> Token {
+        let integer = self.take_while(|c| c.is_ascii_digit());
+        let mut number = integer.to_string();
+        let mut decimal = false;
+        if self.rest().starts_with('.')
+            && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.pos += 1;
+            number.push('.');
+            number.push_str(self.take_while(|c| c.is_ascii_digit()));
+            decimal = true;
+        }
+        let rest = self.rest();
+        if rest.starts_with(['e', 'E']) {
+            let exponent = &rest[1..];
+            let sign = exponent.starts_with(['+', '-']) as usize;
+            if exponent[sign..].starts_with(|c: char| c.is_ascii_digit()) {
+                self.pos += 1 + sign;
+                number.push_str(&rest[..1 + sign]);
+                number.push_str(self.take_while(|c| c.is_ascii_digit()));
+                return Token::Double(number);
+            }
+        }
+        if decimal {
+            Token::Decimal(number)
+        } else {
+            Token::Integer(number)
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, TurtleDocError> {
+        let rest = self.rest();
+        let quote = [r#"""""#, "'''", "\"", "'"]
+            .into_iter()
+            .find(|q| rest.starts_with(q))
+            .ok_or_else(|| self.error("expected a string"))?;
+        let body = &rest[quote.len()..];
+        let mut idx = 0;
+        while idx < body.len() {
+            let r = &body[idx..];
+            if let Some(escaped) = r.strip_prefix('\\') {
+                idx += 1 + escaped.chars().next().map(char::len_utf8).unwrap_or(0);
+            } else if r.starts_with(quote) {
+                self.pos += quote.len() * 2 + idx;
+                return Ok(unescape(&body[..idx]));
+            } else if quote.len() == 1 && r.starts_with(['\n', '\r']) {
+                break;
+            } else {
+                idx += r.chars().next().map(char::len_utf8).unwrap_or(1);
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+fn is_name_start_char(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_var_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || (!c.is_ascii() && !c.is_whitespace())
+}
+
+fn unescape(s: &str) -> String {
+    if !s.contains('\\') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('b') => out.push('\u{8}'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('f') => out.push('\u{c}'),
+            Some(u @ ('u' | 'U')) => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => out.push(c),
+                    None => {
+                        out.push('\\');
+                        out.push(u);
+                        out.push_str(&hex);
+                    }
+                }
+            }
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
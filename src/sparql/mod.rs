@@ -0,0 +1,213 @@
+mod eval;
+mod lexer;
+mod parser;
+mod path;
+mod rules;
+mod update;
+
+use std::collections::BTreeMap;
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::{RdfJsonNodeResult, TurtleDocError};
+
+use crate::{
+    graph::{Graph, Term},
+    iri::is_absolute_iri,
+    obj::{
+        convert_rdf_json_node_result_to_neon_object, load_triples, make_triples_response,
+        read_extra_prefixes, write_triples_in_place, PARAMS_LHS_DATA, PARAMS_LHS_PATH,
+        PARAMS_OUTPUT_FILE_PATH,
+    },
+};
+pub(crate) use eval::{compile_regex, numeric_value, order_terms};
+use eval::{Evaluator, QueryResult};
+use lexer::{Lexer, Token};
+use parser::{Parser, TermPattern};
+pub use path::path;
+pub(crate) use path::{PathEvaluator, PropertyPath};
+pub use rules::reason;
+
+const PARAMS_QUERY: &str = "query";
+const PARAMS_UPDATE: &str = "update";
+const PARAMS_IN_PLACE: &str = "inPlace";
+
+pub fn sparql(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let query: String = match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_QUERY)? {
+        Some(query) => query.value(&mut cx),
+        None => return cx.throw_error(format!("missing query ('{PARAMS_QUERY}')")),
+    };
+    let query = match read_extra_prefixes(&params, &mut cx)
+        .and_then(|prefixes| Parser::new(&query, prefixes))
+        .and_then(|parser| parser.parse_query())
+    {
+        Ok(query) => query,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let mut prefixes = BTreeMap::new();
+    let graph = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => Graph::new(triples),
+        Err(e) => return cx.throw_error(e.message),
+    };
+    match Evaluator::new(&graph).query(&query) {
+        QueryResult::Boolean(b) => Ok(cx.boolean(b).upcast()),
+        QueryResult::Graph(triples) => make_triples_response(&params, &mut cx, &triples, &prefixes),
+        QueryResult::Solutions {
+            variables,
+            solutions,
+        } => make_solutions_response(&mut cx, &variables, solutions),
+    }
+}
+
+pub fn sparql_update(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let update: String = match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_UPDATE)? {
+        Some(update) => update.value(&mut cx),
+        None => return cx.throw_error(format!("missing update ('{PARAMS_UPDATE}')")),
+    };
+    let in_place = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_IN_PLACE)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let in_place_path = if in_place {
+        if params
+            .get_opt::<JsValue, _, _>(&mut cx, PARAMS_OUTPUT_FILE_PATH)?
+            .is_some()
+        {
+            return cx.throw_error(format!(
+                "'{PARAMS_IN_PLACE}' and '{PARAMS_OUTPUT_FILE_PATH}' are mutually exclusive"
+            ));
+        }
+        match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_LHS_PATH)? {
+            Some(path) => Some(path.value(&mut cx)),
+            None => {
+                return cx.throw_error(format!("'{PARAMS_IN_PLACE}' requires '{PARAMS_LHS_PATH}'"))
+            }
+        }
+    } else {
+        None
+    };
+    let operations = match read_extra_prefixes(&params, &mut cx)
+        .and_then(|prefixes| update::parse_update(&update, prefixes))
+    {
+        Ok(operations) => operations,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let mut prefixes = BTreeMap::new();
+    let model = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    )
+    .and_then(|model| update::execute(model, &operations))
+    {
+        Ok(model) => model,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    match in_place_path {
+        Some(path) => match write_triples_in_place(&params, &mut cx, &model, &path) {
+            Ok(_) => Ok(cx.boolean(true).upcast()),
+            Err(e) => cx.throw_error(e.message),
+        },
+        None => make_triples_response(&params, &mut cx, &model, &prefixes),
+    }
+}
+
+/// parses a single term in turtle syntax, e.g <iri>, a prefixed name or a
+/// literal. an absolute iri is accepted without brackets
+pub(crate) fn parse_term(
+    value: &str,
+    prefixes: BTreeMap<String, String>,
+) -> Result<Term, TurtleDocError> {
+    if !value.starts_with(['<', '"', '\'', '_']) && value.contains("://") {
+        return Ok(Term::Iri(value.to_string()));
+    }
+    let mut parser = Parser::new(value, prefixes)?;
+    match parser.parse_term_pattern(false)? {
+        TermPattern::Term(term) if parser.peek().is_none() => Ok(term),
+        _ => Err(TurtleDocError {
+            message: format!("{value} is not a term"),
+        }),
+    }
+}
+
+/// prefixes declared by a turtle source. a redeclared prefix keeps its last
+/// namespace, relative namespaces are appended to the base like tortank does.
+/// the source is tokenized, so declarations in literals and comments are
+/// ignored
+pub(crate) fn prefix_declarations(source: &str) -> BTreeMap<String, String> {
+    let tokens = Lexer::tokenize(source).unwrap_or_default();
+    let mut prefixes = BTreeMap::new();
+    let mut base = String::new();
+    let mut tokens = tokens.into_iter().map(|(token, _)| token).peekable();
+    while let Some(token) = tokens.next() {
+        let directive = match &token {
+            Token::LangTag(d) if d == "prefix" || d == "base" => d.clone(),
+            Token::Keyword(d) if d.eq_ignore_ascii_case("prefix") => "prefix".into(),
+            Token::Keyword(d) if d.eq_ignore_ascii_case("base") => "base".into(),
+            _ => continue,
+        };
+        match (directive.as_str(), tokens.peek()) {
+            ("base", Some(Token::Iri(iri))) => base = iri.clone(),
+            ("prefix", Some(Token::PrefixedName(prefix, local))) if local.is_empty() => {
+                let prefix = prefix.clone();
+                tokens.next();
+                if let Some(Token::Iri(namespace)) = tokens.peek() {
+                    let namespace = if is_absolute_iri(namespace) {
+                        namespace.clone()
+                    } else {
+                        format!("{base}{namespace}")
+                    };
+                    prefixes.insert(prefix, namespace);
+                }
+            }
+            _ => {}
+        }
+    }
+    prefixes
+}
+
+/// SPARQL 1.1 query results json format, unbound variables are omitted
+fn make_solutions_response<'a>(
+    cx: &mut FunctionContext<'a>,
+    variables: &[String],
+    solutions: Vec<Vec<Option<Term>>>,
+) -> JsResult<'a, JsValue> {
+    let vars = JsArray::new(cx, variables.len());
+    for (idx, variable) in variables.iter().enumerate() {
+        let variable = cx.string(variable);
+        vars.set(cx, idx as u32, variable)?;
+    }
+    let head = cx.empty_object();
+    head.set(cx, "vars", vars)?;
+
+    let bindings = JsArray::new(cx, solutions.len());
+    for (idx, row) in solutions.into_iter().enumerate() {
+        let binding = cx.empty_object();
+        for (variable, term) in variables.iter().zip(row) {
+            if let Some(term) = term {
+                let node = convert_rdf_json_node_result_to_neon_object(
+                    cx,
+                    RdfJsonNodeResult::SingleNode((&term).into()),
+                )?;
+                binding.set(cx, variable.as_str(), node)?;
+            }
+        }
+        bindings.set(cx, idx as u32, binding)?;
+    }
+    let results = cx.empty_object();
+    results.set(cx, "bindings", bindings)?;
+
+    let response = cx.empty_object();
+    response.set(cx, "head", head)?;
+    response.set(cx, "results", results)?;
+    Ok(response.upcast())
+}
@@ -0,0 +1,772 @@
+use std::collections::BTreeMap;
+
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+use super::lexer::{Lexer, Token};
+use crate::{
+    graph::{Term, RDF_TYPE, XSD_BOOLEAN, XSD_DECIMAL, XSD_DOUBLE, XSD_INTEGER, XSD_STRING},
+    obj::normalize_literal,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TermPattern {
+    Term(Term),
+    Variable(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriplePattern {
+    pub subject: TermPattern,
+    pub predicate: TermPattern,
+    pub object: TermPattern,
+}
+
+#[derive(Clone, Debug)]
+pub enum GraphPattern {
+    Bgp(Vec<TriplePattern>),
+    Join(Box<GraphPattern>, Box<GraphPattern>),
+    LeftJoin(Box<GraphPattern>, Box<GraphPattern>, Option<Expression>),
+    Union(Box<GraphPattern>, Box<GraphPattern>),
+    Filter(Expression, Box<GraphPattern>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareOp {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArithmeticOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Str,
+    Lang,
+    LangMatches,
+    Datatype,
+    Regex,
+    IsIri,
+    IsBlank,
+    IsLiteral,
+    IsNumeric,
+    SameTerm,
+    Contains,
+    StrStarts,
+    StrEnds,
+    LCase,
+    UCase,
+    StrLen,
+}
+
+impl Function {
+    fn from_keyword(keyword: &str) -> Option<(Function, usize, usize)> {
+        let f = match keyword.to_ascii_uppercase().as_str() {
+            "STR" => (Function::Str, 1, 1),
+            "LANG" => (Function::Lang, 1, 1),
+            "LANGMATCHES" => (Function::LangMatches, 2, 2),
+            "DATATYPE" => (Function::Datatype, 1, 1),
+            "REGEX" => (Function::Regex, 2, 3),
+            "ISIRI" | "ISURI" => (Function::IsIri, 1, 1),
+            "ISBLANK" => (Function::IsBlank, 1, 1),
+            "ISLITERAL" => (Function::IsLiteral, 1, 1),
+            "ISNUMERIC" => (Function::IsNumeric, 1, 1),
+            "SAMETERM" => (Function::SameTerm, 2, 2),
+            "CONTAINS" => (Function::Contains, 2, 2),
+            "STRSTARTS" => (Function::StrStarts, 2, 2),
+            "STRENDS" => (Function::StrEnds, 2, 2),
+            "LCASE" => (Function::LCase, 1, 1),
+            "UCASE" => (Function::UCase, 1, 1),
+            "STRLEN" => (Function::StrLen, 1, 1),
+            _ => return None,
+        };
+        Some(f)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Constant(Term),
+    Variable(String),
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(CompareOp, Box<Expression>, Box<Expression>),
+    Arithmetic(ArithmeticOp, Box<Expression>, Box<Expression>),
+    Negate(Box<Expression>),
+    In(Box<Expression>, Vec<Expression>, bool),
+    Bound(String),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Clone, Debug)]
+pub enum OrderCondition {
+    Asc(Expression),
+    Desc(Expression),
+}
+
+#[derive(Clone, Debug)]
+pub enum QueryForm {
+    Select {
+        distinct: bool,
+        variables: Option<Vec<String>>,
+    },
+    Ask,
+    Construct(Vec<TriplePattern>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Query {
+    pub form: QueryForm,
+    pub pattern: GraphPattern,
+    pub order_by: Vec<OrderCondition>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+pub struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    base: Option<String>,
+    prefixes: BTreeMap<String, String>,
+    anonymous_count: usize,
+}
+
+impl Parser {
+    pub fn new(input: &str, prefixes: BTreeMap<String, String>) -> Result<Parser, TurtleDocError> {
+        Ok(Parser {
+            tokens: Lexer::tokenize(input)?,
+            pos: 0,
+            base: None,
+            prefixes,
+            anonymous_count: 0,
+        })
+    }
+
+    pub fn parse_query(mut self) -> Result<Query, TurtleDocError> {
+        self.parse_prologue()?;
+        let form = if self.eat_keyword("SELECT") {
+            let distinct = self.eat_keyword("DISTINCT") || self.eat_keyword("REDUCED");
+            let variables = if self.eat_punct("*") {
+                None
+            } else {
+                let mut variables = vec![];
+                while let Some(Token::Variable(v)) = self.peek() {
+                    variables.push(v.clone());
+                    self.pos += 1;
+                }
+                if variables.is_empty() {
+                    return Err(self.error("expected variables or *"));
+                }
+                Some(variables)
+            };
+            QueryForm::Select {
+                distinct,
+                variables,
+            }
+        } else if self.eat_keyword("ASK") {
+            QueryForm::Ask
+        } else if self.eat_keyword("CONSTRUCT") {
+            if self.peek_keyword("WHERE") {
+                // short form, the template is the pattern itself
+                QueryForm::Construct(vec![])
+            } else {
+                QueryForm::Construct(self.parse_template()?)
+            }
+        } else {
+            return Err(self.error("expected SELECT, ASK or CONSTRUCT"));
+        };
+        self.eat_keyword("WHERE");
+        let pattern = self.parse_group_graph_pattern()?;
+        let form = match form {
+            QueryForm::Construct(template) if template.is_empty() => {
+                let GraphPattern::Bgp(bgp) = &pattern else {
+                    return Err(self.error("CONSTRUCT WHERE only accepts triple patterns"));
+                };
+                QueryForm::Construct(bgp.clone())
+            }
+            form => form,
+        };
+
+        let mut order_by = vec![];
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                if self.eat_keyword("ASC") {
+                    order_by.push(OrderCondition::Asc(self.parse_bracketted_expression()?));
+                } else if self.eat_keyword("DESC") {
+                    order_by.push(OrderCondition::Desc(self.parse_bracketted_expression()?));
+                } else if let Some(Token::Variable(v)) = self.peek() {
+                    order_by.push(OrderCondition::Asc(Expression::Variable(v.clone())));
+                    self.pos += 1;
+                } else if self.peek_punct("(") || self.peek_function() {
+                    order_by.push(OrderCondition::Asc(self.parse_primary_expression()?));
+                } else {
+                    break;
+                }
+            }
+            if order_by.is_empty() {
+                return Err(self.error("expected an order condition"));
+            }
+        }
+        let mut limit = None;
+        let mut offset = 0;
+        loop {
+            if self.eat_keyword("LIMIT") {
+                limit = Some(self.parse_usize()?);
+            } else if self.eat_keyword("OFFSET") {
+                offset = self.parse_usize()?;
+            } else {
+                break;
+            }
+        }
+        if self.peek().is_some() {
+            return Err(self.error("unexpected token"));
+        }
+        Ok(Query {
+            form,
+            pattern,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    /// PREFIX and BASE, or the turtle @prefix and @base directives used by n3
+    pub fn parse_prologue(&mut self) -> Result<(), TurtleDocError> {
+        loop {
+            let prefix_directive = self.eat_directive("prefix");
+            let base_directive = !prefix_directive && self.eat_directive("base");
+            if prefix_directive || self.eat_keyword("PREFIX") {
+                let prefix = match self.next() {
+                    Some(Token::PrefixedName(prefix, local)) if local.is_empty() => prefix,
+                    _ => return Err(self.error("expected a prefix")),
+                };
+                let iri = match self.next() {
+                    Some(Token::Iri(iri)) => self.resolve(iri),
+                    _ => return Err(self.error("expected an iri")),
+                };
+                self.prefixes.insert(prefix, iri);
+            } else if base_directive || self.eat_keyword("BASE") {
+                match self.next() {
+                    Some(Token::Iri(iri)) => self.base = Some(self.resolve(iri)),
+                    _ => return Err(self.error("expected an iri")),
+                }
+            } else {
+                return Ok(());
+            }
+            if prefix_directive || base_directive {
+                self.expect_punct(".")?;
+            }
+        }
+    }
+
+    fn eat_directive(&mut self, directive: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::LangTag(d)) if d == directive);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub fn parse_template(&mut self) -> Result<Vec<TriplePattern>, TurtleDocError> {
+        self.expect_punct("{")?;
+        let mut triples = vec![];
+        while !self.eat_punct("}") {
+            if self.peek().is_none() {
+                return Err(self.error("unterminated template"));
+            }
+            self.parse_triples_same_subject(&mut triples, false)?;
+            if !self.eat_punct(".") && !self.peek_punct("}") {
+                return Err(self.error("expected . or }"));
+            }
+        }
+        Ok(triples)
+    }
+
+    /// group graph pattern translated to the sparql algebra
+    pub fn parse_group_graph_pattern(&mut self) -> Result<GraphPattern, TurtleDocError> {
+        self.expect_punct("{")?;
+        let mut group: Option<GraphPattern> = None;
+        let mut filters: Vec<Expression> = vec![];
+        let join = |group: Option<GraphPattern>, pattern: GraphPattern| match group {
+            None => pattern,
+            Some(GraphPattern::Bgp(mut lhs)) => match pattern {
+                GraphPattern::Bgp(rhs) => {
+                    lhs.extend(rhs);
+                    GraphPattern::Bgp(lhs)
+                }
+                pattern => GraphPattern::Join(Box::new(GraphPattern::Bgp(lhs)), Box::new(pattern)),
+            },
+            Some(group) => GraphPattern::Join(Box::new(group), Box::new(pattern)),
+        };
+        loop {
+            if self.eat_punct("}") {
+                break;
+            } else if self.peek().is_none() {
+                return Err(self.error("unterminated group"));
+            } else if self.eat_punct(".") {
+                continue;
+            } else if self.eat_keyword("FILTER") {
+                filters.push(self.parse_constraint()?);
+            } else if self.eat_keyword("OPTIONAL") {
+                let optional = self.parse_group_graph_pattern()?;
+                let (optional, expression) = match optional {
+                    GraphPattern::Filter(expression, inner) => (*inner, Some(expression)),
+                    optional => (optional, None),
+                };
+                group = Some(GraphPattern::LeftJoin(
+                    Box::new(group.unwrap_or(GraphPattern::Bgp(vec![]))),
+                    Box::new(optional),
+                    expression,
+                ));
+            } else if self.peek_punct("{") {
+                let mut pattern = self.parse_group_graph_pattern()?;
+                while self.eat_keyword("UNION") {
+                    let rhs = self.parse_group_graph_pattern()?;
+                    pattern = GraphPattern::Union(Box::new(pattern), Box::new(rhs));
+                }
+                group = Some(join(group, pattern));
+            } else {
+                let mut triples = vec![];
+                self.parse_triples_same_subject(&mut triples, true)?;
+                group = Some(join(group, GraphPattern::Bgp(triples)));
+                if !self.eat_punct(".")
+                    && !self.peek_punct("}")
+                    && !self.peek_graph_pattern_not_triples()
+                {
+                    return Err(self.error("expected . or }"));
+                }
+            }
+        }
+        let mut group = group.unwrap_or(GraphPattern::Bgp(vec![]));
+        if let Some(filter) = filters
+            .into_iter()
+            .reduce(|lhs, rhs| Expression::And(Box::new(lhs), Box::new(rhs)))
+        {
+            group = GraphPattern::Filter(filter, Box::new(group));
+        }
+        Ok(group)
+    }
+
+    fn peek_graph_pattern_not_triples(&self) -> bool {
+        self.peek_punct("{") || self.peek_keyword("OPTIONAL") || self.peek_keyword("FILTER")
+    }
+
+    /// blank nodes are variables in a pattern, and blank nodes in a template
+    pub fn parse_triples_same_subject(
+        &mut self,
+        triples: &mut Vec<TriplePattern>,
+        blank_nodes_as_variables: bool,
+    ) -> Result<(), TurtleDocError> {
+        let subject = if self.eat_punct("[") {
+            let subject = self.anonymous(blank_nodes_as_variables);
+            if self.eat_punct("]") {
+                subject
+            } else {
+                self.parse_property_list(&subject, triples, blank_nodes_as_variables)?;
+                self.expect_punct("]")?;
+                // [ :p :o ] . is a valid triples block on its own
+                if self.peek_punct(".") || self.peek_punct("}") {
+                    return Ok(());
+                }
+                subject
+            }
+        } else {
+            self.parse_term_pattern(blank_nodes_as_variables)?
+        };
+        self.parse_property_list(&subject, triples, blank_nodes_as_variables)
+    }
+
+    fn parse_property_list(
+        &mut self,
+        subject: &TermPattern,
+        triples: &mut Vec<TriplePattern>,
+        blank_nodes_as_variables: bool,
+    ) -> Result<(), TurtleDocError> {
+        loop {
+            let predicate = if self.eat_keyword("a") {
+                TermPattern::Term(Term::Iri(RDF_TYPE.into()))
+            } else {
+                self.parse_term_pattern(blank_nodes_as_variables)?
+            };
+            loop {
+                let object = self.parse_object(triples, blank_nodes_as_variables)?;
+                triples.push(TriplePattern {
+                    subject: subject.clone(),
+                    predicate: predicate.clone(),
+                    object,
+                });
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+            if !self.eat_punct(";") {
+                return Ok(());
+            }
+            while self.eat_punct(";") {}
+            if self.peek_punct(".") || self.peek_punct("]") || self.peek_punct("}") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_object(
+        &mut self,
+        triples: &mut Vec<TriplePattern>,
+        blank_nodes_as_variables: bool,
+    ) -> Result<TermPattern, TurtleDocError> {
+        if self.eat_punct("[") {
+            let node = self.anonymous(blank_nodes_as_variables);
+            if !self.eat_punct("]") {
+                self.parse_property_list(&node, triples, blank_nodes_as_variables)?;
+                self.expect_punct("]")?;
+            }
+            Ok(node)
+        } else {
+            self.parse_term_pattern(blank_nodes_as_variables)
+        }
+    }
+
+    fn anonymous(&mut self, blank_nodes_as_variables: bool) -> TermPattern {
+        self.anonymous_count += 1;
+        let label = format!("anon{}", self.anonymous_count);
+        if blank_nodes_as_variables {
+            TermPattern::Variable(format!("_:{label}"))
+        } else {
+            TermPattern::Term(Term::BlankNode(label))
+        }
+    }
+
+    pub fn parse_term_pattern(
+        &mut self,
+        blank_nodes_as_variables: bool,
+    ) -> Result<TermPattern, TurtleDocError> {
+        match self.peek().cloned() {
+            Some(Token::Variable(v)) => {
+                self.pos += 1;
+                Ok(TermPattern::Variable(v))
+            }
+            Some(Token::BlankNode(label)) => {
+                self.pos += 1;
+                if blank_nodes_as_variables {
+                    Ok(TermPattern::Variable(format!("_:{label}")))
+                } else {
+                    Ok(TermPattern::Term(Term::BlankNode(label)))
+                }
+            }
+            Some(Token::Punct("(")) => Err(self.error("collections are not supported")),
+            _ => self.parse_term().map(TermPattern::Term),
+        }
+    }
+
+    /// iri or literal
+    pub fn parse_term(&mut self) -> Result<Term, TurtleDocError> {
+        let negative = if self.peek_punct("-") || self.peek_punct("+") {
+            let negative = self.peek_punct("-");
+            match self.tokens.get(self.pos + 1).map(|t| &t.0) {
+                Some(Token::Integer(_) | Token::Decimal(_) | Token::Double(_)) => {
+                    self.pos += 1;
+                    negative
+                }
+                _ => return Err(self.error("expected a term")),
+            }
+        } else {
+            false
+        };
+        let sign = if negative { "-" } else { "" };
+        let term = match self.next() {
+            Some(Token::Iri(iri)) => Term::Iri(self.resolve(iri)),
+            Some(Token::PrefixedName(prefix, local)) => Term::Iri(self.expand(&prefix, &local)?),
+            Some(Token::Integer(i)) => literal(format!("{sign}{i}"), XSD_INTEGER),
+            Some(Token::Decimal(d)) => literal(format!("{sign}{d}"), XSD_DECIMAL),
+            Some(Token::Double(d)) => literal(format!("{sign}{d}"), XSD_DOUBLE),
+            Some(Token::Keyword(k)) if k == "true" || k == "false" => literal(k, XSD_BOOLEAN),
+            Some(Token::String(value)) => {
+                if let Some(Token::LangTag(lang)) = self.peek().cloned() {
+                    self.pos += 1;
+                    Term::Literal {
+                        value,
+                        datatype: None,
+                        lang: Some(lang),
+                    }
+                } else if self.eat_punct("^^") {
+                    let datatype = match self.next() {
+                        Some(Token::Iri(iri)) => self.resolve(iri),
+                        Some(Token::PrefixedName(prefix, local)) => self.expand(&prefix, &local)?,
+                        _ => return Err(self.error("expected a datatype")),
+                    };
+                    literal(value, &datatype)
+                } else {
+                    literal(value, XSD_STRING)
+                }
+            }
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                return Err(self.error("expected a term"));
+            }
+        };
+        Ok(normalize_literal(term))
+    }
+
+    fn parse_constraint(&mut self) -> Result<Expression, TurtleDocError> {
+        if self.peek_punct("(") {
+            self.parse_bracketted_expression()
+        } else if self.peek_function() {
+            self.parse_primary_expression()
+        } else {
+            Err(self.error("expected a constraint"))
+        }
+    }
+
+    fn parse_bracketted_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        self.expect_punct("(")?;
+        let expression = self.parse_expression()?;
+        self.expect_punct(")")?;
+        Ok(expression)
+    }
+
+    pub fn parse_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        let mut lhs = self.parse_and_expression()?;
+        while self.eat_punct("||") {
+            let rhs = self.parse_and_expression()?;
+            lhs = Expression::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        let mut lhs = self.parse_relational_expression()?;
+        while self.eat_punct("&&") {
+            let rhs = self.parse_relational_expression()?;
+            lhs = Expression::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_relational_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        let lhs = self.parse_additive_expression()?;
+        let op = [
+            ("=", CompareOp::Equal),
+            ("!=", CompareOp::NotEqual),
+            ("<", CompareOp::Less),
+            (">", CompareOp::Greater),
+            ("<=", CompareOp::LessOrEqual),
+            (">=", CompareOp::GreaterOrEqual),
+        ]
+        .into_iter()
+        .find(|(p, _)| self.peek_punct(p));
+        if let Some((_, op)) = op {
+            self.pos += 1;
+            let rhs = self.parse_additive_expression()?;
+            return Ok(Expression::Compare(op, Box::new(lhs), Box::new(rhs)));
+        }
+        let negated = if self.peek_keyword("NOT")
+            && matches!(self.tokens.get(self.pos + 1), Some((Token::Keyword(k), _)) if k.eq_ignore_ascii_case("IN"))
+        {
+            self.pos += 2;
+            true
+        } else if self.eat_keyword("IN") {
+            false
+        } else {
+            return Ok(lhs);
+        };
+        self.expect_punct("(")?;
+        let mut list = vec![];
+        while !self.eat_punct(")") {
+            list.push(self.parse_expression()?);
+            if !self.eat_punct(",") && !self.peek_punct(")") {
+                return Err(self.error("expected , or )"));
+            }
+        }
+        Ok(Expression::In(Box::new(lhs), list, negated))
+    }
+
+    fn parse_additive_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        let mut lhs = self.parse_multiplicative_expression()?;
+        loop {
+            let op = if self.eat_punct("+") {
+                ArithmeticOp::Add
+            } else if self.eat_punct("-") {
+                ArithmeticOp::Subtract
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_multiplicative_expression()?;
+            lhs = Expression::Arithmetic(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_multiplicative_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        let mut lhs = self.parse_unary_expression()?;
+        loop {
+            let op = if self.eat_punct("*") {
+                ArithmeticOp::Multiply
+            } else if self.eat_punct("/") {
+                ArithmeticOp::Divide
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_unary_expression()?;
+            lhs = Expression::Arithmetic(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        if self.eat_punct("!") {
+            Ok(Expression::Not(Box::new(self.parse_unary_expression()?)))
+        } else if self.eat_punct("-") {
+            Ok(Expression::Negate(Box::new(self.parse_unary_expression()?)))
+        } else if self.eat_punct("+") {
+            self.parse_unary_expression()
+        } else {
+            self.parse_primary_expression()
+        }
+    }
+
+    fn peek_function(&self) -> bool {
+        matches!(self.peek(), Some(Token::Keyword(k)) if k.eq_ignore_ascii_case("BOUND") || Function::from_keyword(k).is_some())
+    }
+
+    fn parse_primary_expression(&mut self) -> Result<Expression, TurtleDocError> {
+        match self.peek().cloned() {
+            Some(Token::Punct("(")) => self.parse_bracketted_expression(),
+            Some(Token::Variable(v)) => {
+                self.pos += 1;
+                Ok(Expression::Variable(v))
+            }
+            Some(Token::Keyword(k)) if k.eq_ignore_ascii_case("BOUND") => {
+                self.pos += 1;
+                self.expect_punct("(")?;
+                let variable = match self.next() {
+                    Some(Token::Variable(v)) => v,
+                    _ => return Err(self.error("expected a variable")),
+                };
+                self.expect_punct(")")?;
+                Ok(Expression::Bound(variable))
+            }
+            Some(Token::Keyword(k)) if Function::from_keyword(&k).is_some() => {
+                let (function, min, max) =
+                    Function::from_keyword(&k).unwrap_or((Function::Str, 1, 1));
+                self.pos += 1;
+                self.expect_punct("(")?;
+                let mut args = vec![];
+                while !self.eat_punct(")") {
+                    args.push(self.parse_expression()?);
+                    if !self.eat_punct(",") && !self.peek_punct(")") {
+                        return Err(self.error("expected , or )"));
+                    }
+                }
+                if args.len() < min || args.len() > max {
+                    return Err(self.error(format!("wrong number of arguments for {k}")));
+                }
+                Ok(Expression::Call(function, args))
+            }
+            _ => {
+                let term = self.parse_term()?;
+                if self.peek_punct("(") {
+                    return Err(self.error(format!("function {term} is not supported")));
+                }
+                Ok(Expression::Constant(term))
+            }
+        }
+    }
+
+    fn parse_usize(&mut self) -> Result<usize, TurtleDocError> {
+        match self.next() {
+            Some(Token::Integer(i)) => i.parse().map_err(|_| self.error("invalid number")),
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    pub fn resolve(&self, iri: String) -> String {
+        match &self.base {
+            Some(base) if !iri.contains(':') => format!("{base}{iri}"),
+            _ => iri,
+        }
+    }
+
+    pub fn expand(&self, prefix: &str, local: &str) -> Result<String, TurtleDocError> {
+        self.prefixes
+            .get(prefix)
+            .map(|iri| format!("{iri}{local}"))
+            .ok_or_else(|| self.error(format!("prefix {prefix} unknown")))
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    pub fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    pub fn peek_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    pub fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.peek_punct(punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub fn expect_punct(&mut self, punct: &str) -> Result<(), TurtleDocError> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {punct}")))
+        }
+    }
+
+    pub fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Keyword(k)) if k.eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub fn expect_keyword(&mut self, keyword: &str) -> Result<(), TurtleDocError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {keyword}")))
+        }
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> TurtleDocError {
+        let position = match self.tokens.get(self.pos) {
+            Some((token, position)) => format!("{token:?} at position {position}"),
+            None => "end of input".into(),
+        };
+        TurtleDocError {
+            message: format!("{}, found {position}", message.into()),
+        }
+    }
+}
+
+fn literal(value: impl Into<String>, datatype: &str) -> Term {
+    Term::Literal {
+        value: value.into(),
+        datatype: Some(datatype.to_string()),
+        lang: None,
+    }
+}
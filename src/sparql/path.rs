@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::{RdfJsonNodeResult, TurtleDocError};
+
+use super::{lexer::Token, parse_term, parser::Parser};
+use crate::{
+    graph::{Graph, Term, Triple, RDF_TYPE},
+    obj::{
+        convert_rdf_json_node_result_to_neon_object, load_triples, make_triples_response,
+        PARAMS_LHS_DATA, PARAMS_LHS_PATH,
+    },
+};
+
+const PARAMS_START: &str = "start";
+const PARAMS_PATH: &str = "path";
+const PARAMS_TRIPLES: &str = "triples";
+
+pub enum PropertyPath {
+    Predicate(Term),
+    Inverse(Box<PropertyPath>),
+    Sequence(Box<PropertyPath>, Box<PropertyPath>),
+    Alternative(Box<PropertyPath>, Box<PropertyPath>),
+    ZeroOrOne(Box<PropertyPath>),
+    ZeroOrMore(Box<PropertyPath>),
+    OneOrMore(Box<PropertyPath>),
+}
+
+/// sequence '/', alternative '|', inverse '^', '*', '+', '?' and parentheses
+pub fn parse_path(
+    input: &str,
+    prefixes: BTreeMap<String, String>,
+) -> Result<PropertyPath, TurtleDocError> {
+    let mut parser = Parser::new(input, prefixes)?;
+    let path = parse_alternative(&mut parser)?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected token in path"));
+    }
+    Ok(path)
+}
+
+fn parse_alternative(parser: &mut Parser) -> Result<PropertyPath, TurtleDocError> {
+    let mut path = parse_sequence(parser)?;
+    while parser.eat_punct("|") {
+        let right = parse_sequence(parser)?;
+        path = PropertyPath::Alternative(Box::new(path), Box::new(right));
+    }
+    Ok(path)
+}
+
+fn parse_sequence(parser: &mut Parser) -> Result<PropertyPath, TurtleDocError> {
+    let mut path = parse_element(parser)?;
+    while parser.eat_punct("/") {
+        let right = parse_element(parser)?;
+        path = PropertyPath::Sequence(Box::new(path), Box::new(right));
+    }
+    Ok(path)
+}
+
+fn parse_element(parser: &mut Parser) -> Result<PropertyPath, TurtleDocError> {
+    if parser.eat_punct("^") {
+        return Ok(PropertyPath::Inverse(Box::new(parse_element(parser)?)));
+    }
+    let path = if parser.eat_punct("(") {
+        let path = parse_alternative(parser)?;
+        parser.expect_punct(")")?;
+        path
+    } else if parser.eat_keyword("a") {
+        PropertyPath::Predicate(Term::Iri(RDF_TYPE.into()))
+    } else {
+        match parser.next() {
+            Some(Token::Iri(iri)) => PropertyPath::Predicate(Term::Iri(parser.resolve(iri))),
+            Some(Token::PrefixedName(prefix, local)) => {
+                PropertyPath::Predicate(Term::Iri(parser.expand(&prefix, &local)?))
+            }
+            _ => return Err(parser.error("expected an iri in path")),
+        }
+    };
+    Ok(if parser.eat_punct("*") {
+        PropertyPath::ZeroOrMore(Box::new(path))
+    } else if parser.eat_punct("+") {
+        PropertyPath::OneOrMore(Box::new(path))
+    } else if parser.eat_punct("?") {
+        PropertyPath::ZeroOrOne(Box::new(path))
+    } else {
+        path
+    })
+}
+
+/// nodes reached by a path in order of discovery, with the triples of the
+/// paths leading to each of them
+#[derive(Default)]
+pub struct Reached {
+    pub nodes: Vec<Term>,
+    pub triples: HashMap<Term, HashSet<Triple>>,
+}
+
+impl Reached {
+    /// returns true if the node is new or new triples lead to it
+    fn add(&mut self, node: &Term, triples: impl IntoIterator<Item = Triple>) -> bool {
+        match self.triples.get_mut(node) {
+            Some(existing) => {
+                let len = existing.len();
+                existing.extend(triples);
+                existing.len() > len
+            }
+            None => {
+                self.nodes.push(node.clone());
+                self.triples
+                    .insert(node.clone(), triples.into_iter().collect());
+                true
+            }
+        }
+    }
+}
+
+/// evaluates property paths from a node. when triples are not tracked, only
+/// the reached nodes are computed.
+pub struct PathEvaluator<'a> {
+    graph: &'a Graph,
+    track_triples: bool,
+}
+
+impl<'a> PathEvaluator<'a> {
+    pub fn new(graph: &'a Graph, track_triples: bool) -> PathEvaluator<'a> {
+        PathEvaluator {
+            graph,
+            track_triples,
+        }
+    }
+
+    pub fn eval(&self, path: &PropertyPath, start: &Term) -> Reached {
+        self.eval_directed(path, start, false)
+    }
+
+    fn eval_directed(&self, path: &PropertyPath, start: &Term, inverse: bool) -> Reached {
+        let mut reached = Reached::default();
+        match path {
+            PropertyPath::Predicate(predicate) => {
+                let triples = if inverse {
+                    self.graph.matching(None, Some(predicate), Some(start))
+                } else {
+                    self.graph.matching(Some(start), Some(predicate), None)
+                };
+                for triple in triples {
+                    let node = if inverse {
+                        &triple.subject
+                    } else {
+                        &triple.object
+                    };
+                    reached.add(node, self.tracked([triple]));
+                }
+            }
+            PropertyPath::Inverse(path) => return self.eval_directed(path, start, !inverse),
+            PropertyPath::Sequence(first, second) => {
+                // ^(a/b) is ^b/^a
+                let (first, second) = if inverse {
+                    (second, first)
+                } else {
+                    (first, second)
+                };
+                let head = self.eval_directed(first, start, inverse);
+                for middle in &head.nodes {
+                    let tail = self.eval_directed(second, middle, inverse);
+                    for node in &tail.nodes {
+                        reached.add(
+                            node,
+                            self.tracked(head.triples[middle].iter().chain(&tail.triples[node])),
+                        );
+                    }
+                }
+            }
+            PropertyPath::Alternative(left, right) => {
+                for side in [left, right] {
+                    let side = self.eval_directed(side, start, inverse);
+                    for node in &side.nodes {
+                        reached.add(node, side.triples[node].iter().cloned());
+                    }
+                }
+            }
+            PropertyPath::ZeroOrOne(path) => {
+                reached.add(start, []);
+                let step = self.eval_directed(path, start, inverse);
+                for node in &step.nodes {
+                    reached.add(node, step.triples[node].iter().cloned());
+                }
+            }
+            PropertyPath::ZeroOrMore(path) => {
+                reached.add(start, []);
+                self.closure(path, start, inverse, &mut reached);
+            }
+            PropertyPath::OneOrMore(path) => self.closure(path, start, inverse, &mut reached),
+        }
+        reached
+    }
+
+    /// repeats the path until no new node or triple is found, so cycles end
+    /// the traversal
+    fn closure(&self, path: &PropertyPath, start: &Term, inverse: bool, reached: &mut Reached) {
+        let mut steps: HashMap<Term, Reached> = HashMap::new();
+        let mut queue = VecDeque::from([start.clone()]);
+        while let Some(from) = queue.pop_front() {
+            let step = steps
+                .entry(from.clone())
+                .or_insert_with(|| self.eval_directed(path, &from, inverse));
+            let before: Vec<Triple> = reached
+                .triples
+                .get(&from)
+                .map(|t| t.iter().cloned().collect())
+                .unwrap_or_default();
+            for node in &step.nodes {
+                let triples = self.tracked(before.iter().chain(&step.triples[node]));
+                if reached.add(node, triples) && !queue.contains(node) {
+                    queue.push_back(node.clone());
+                }
+            }
+        }
+    }
+
+    fn tracked<'b>(&self, triples: impl IntoIterator<Item = &'b Triple>) -> Vec<Triple> {
+        if self.track_triples {
+            triples.into_iter().cloned().collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+pub fn path(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let start: String = match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_START)? {
+        Some(start) => start.value(&mut cx),
+        None => return cx.throw_error(format!("missing start node ('{PARAMS_START}')")),
+    };
+    let path: String = match params.get_opt::<JsString, _, _>(&mut cx, PARAMS_PATH)? {
+        Some(path) => path.value(&mut cx),
+        None => return cx.throw_error(format!("missing path ('{PARAMS_PATH}')")),
+    };
+    let with_triples = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_TRIPLES)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let (start, path) = match parse_term(&start, prefixes.clone())
+        .and_then(|start| Ok((start, parse_path(&path, prefixes.clone())?)))
+    {
+        Ok(parsed) => parsed,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let graph = Graph::new(triples.iter().cloned());
+    let reached = PathEvaluator::new(&graph, with_triples).eval(&path, &start);
+
+    if with_triples {
+        // in the order of the model
+        let mut traversed: HashSet<Triple> = reached.triples.into_values().flatten().collect();
+        let triples: Vec<Triple> = triples
+            .into_iter()
+            .filter(|t| traversed.remove(t))
+            .collect();
+        return make_triples_response(&params, &mut cx, &triples, &prefixes);
+    }
+    let array = JsArray::new(&mut cx, reached.nodes.len());
+    for (idx, node) in reached.nodes.iter().enumerate() {
+        let node = convert_rdf_json_node_result_to_neon_object(
+            &mut cx,
+            RdfJsonNodeResult::SingleNode(node.into()),
+        )?;
+        array.set(&mut cx, idx as u32, node)?;
+    }
+    Ok(array.upcast())
+}
@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, HashSet};
+
+use neon::prelude::*;
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+use super::{
+    eval::{instantiate, Evaluator, Solution},
+    parser::{GraphPattern, Parser, TriplePattern},
+    update::{blank_node_labels, fresh_label},
+};
+use crate::{
+    graph::{Graph, Term, Triple},
+    obj::{
+        load_triples, make_triples_response, read_extra_prefixes, PARAMS_LHS_DATA, PARAMS_LHS_PATH,
+    },
+};
+
+const PARAMS_RULES_PATH: &str = "rulesPath";
+const PARAMS_RULES_DATA: &str = "rulesData";
+const PARAMS_MAX_ITERATIONS: &str = "maxIterations";
+
+const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+/// { body } => { head } . blank nodes of the body are variables, blank
+/// nodes of the head are fresh for each firing
+pub struct Rule {
+    body: GraphPattern,
+    head: Vec<TriplePattern>,
+}
+
+pub fn parse_rules(
+    input: &str,
+    prefixes: BTreeMap<String, String>,
+) -> Result<Vec<Rule>, TurtleDocError> {
+    let mut parser = Parser::new(input, prefixes)?;
+    let mut rules = vec![];
+    loop {
+        parser.parse_prologue()?;
+        if parser.peek().is_none() {
+            break;
+        }
+        let body = parser.parse_group_graph_pattern()?;
+        parser.expect_punct("=>")?;
+        let head = parser.parse_template()?;
+        rules.push(Rule { body, head });
+        if !parser.eat_punct(".") && parser.peek().is_some() {
+            return Err(parser.error("expected ."));
+        }
+    }
+    Ok(rules)
+}
+
+/// semi-naive forward chaining: after the first iteration, a rule only looks
+/// for solutions using at least one triple inferred by the previous
+/// iteration. returns the inferred triples, or an error when there is no
+/// fixpoint after max_iterations.
+pub fn forward_chain(
+    model: Vec<Triple>,
+    rules: &[Rule],
+    max_iterations: usize,
+) -> Result<Vec<Triple>, TurtleDocError> {
+    let labels = blank_node_labels(&model);
+    let mut graph = Graph::new(model);
+    let mut delta = vec![];
+    let mut fired: Vec<HashSet<Vec<(String, Term)>>> = vec![HashSet::new(); rules.len()];
+    let mut inferred = vec![];
+    let mut row = 0;
+    for iteration in 0.. {
+        if delta.is_empty() && iteration > 0 {
+            break;
+        }
+        if iteration == max_iterations {
+            return Err(TurtleDocError {
+                message: format!("no fixpoint after {max_iterations} iterations"),
+            });
+        }
+        let delta_graph = Graph::new(std::mem::take(&mut delta));
+        let mut derived = vec![];
+        for (rule, fired) in rules.iter().zip(fired.iter_mut()) {
+            let solutions = if iteration == 0 {
+                Evaluator::new(&graph).eval(&rule.body, vec![Solution::new()])
+            } else {
+                solutions_with_delta(&graph, &delta_graph, &rule.body)
+            };
+            for solution in solutions {
+                let mut key: Vec<(String, Term)> = solution.clone().into_iter().collect();
+                key.sort();
+                if !fired.insert(key) {
+                    continue;
+                }
+                derived.extend(
+                    rule.head.iter().filter_map(|t| {
+                        instantiate(t, &solution, |l| fresh_label(l, row, &labels))
+                    }),
+                );
+                row += 1;
+            }
+        }
+        delta = derived
+            .into_iter()
+            .filter(|t| graph.insert(t.clone()))
+            .collect();
+        inferred.extend(delta.iter().cloned());
+    }
+    Ok(inferred)
+}
+
+/// solutions of a basic graph pattern, optionally filtered, where one of the
+/// triple patterns matches the delta. other bodies are evaluated again on the
+/// whole graph.
+fn solutions_with_delta(graph: &Graph, delta: &Graph, body: &GraphPattern) -> Vec<Solution> {
+    let (patterns, filter) = match body {
+        GraphPattern::Bgp(patterns) => (patterns, None),
+        GraphPattern::Filter(expression, inner) => match inner.as_ref() {
+            GraphPattern::Bgp(patterns) => (patterns, Some(expression)),
+            _ => return Evaluator::new(graph).eval(body, vec![Solution::new()]),
+        },
+        _ => return Evaluator::new(graph).eval(body, vec![Solution::new()]),
+    };
+    let mut solutions = vec![];
+    for (idx, pattern) in patterns.iter().enumerate() {
+        let seeds = Evaluator::new(delta).eval(
+            &GraphPattern::Bgp(vec![pattern.clone()]),
+            vec![Solution::new()],
+        );
+        if seeds.is_empty() {
+            continue;
+        }
+        let mut rest = GraphPattern::Bgp(
+            patterns
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != idx)
+                .map(|(_, p)| p.clone())
+                .collect(),
+        );
+        if let Some(expression) = filter {
+            rest = GraphPattern::Filter(expression.clone(), Box::new(rest));
+        }
+        solutions.extend(Evaluator::new(graph).eval(&rest, seeds));
+    }
+    solutions
+}
+
+pub fn reason(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let rules: String =
+        if let Some(path) = params.get_opt::<JsString, _, _>(&mut cx, PARAMS_RULES_PATH)? {
+            match std::fs::read_to_string(path.value(&mut cx)) {
+                Ok(rules) => rules,
+                Err(e) => return cx.throw_error(format!("cannot read rules: {e}")),
+            }
+        } else if let Some(data) = params.get_opt::<JsString, _, _>(&mut cx, PARAMS_RULES_DATA)? {
+            data.value(&mut cx)
+        } else {
+            return cx.throw_error(format!(
+                "missing rules ('{PARAMS_RULES_PATH}' or '{PARAMS_RULES_DATA}')"
+            ));
+        };
+    let max_iterations = params
+        .get_opt::<JsNumber, _, _>(&mut cx, PARAMS_MAX_ITERATIONS)?
+        .map(|n| n.value(&mut cx).max(1.) as usize)
+        .unwrap_or(DEFAULT_MAX_ITERATIONS);
+    let rules = match read_extra_prefixes(&params, &mut cx)
+        .and_then(|prefixes| parse_rules(&rules, prefixes))
+    {
+        Ok(rules) => rules,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let mut prefixes = BTreeMap::new();
+    let inferred = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    )
+    .and_then(|model| forward_chain(model, &rules, max_iterations))
+    {
+        Ok(inferred) => inferred,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    make_triples_response(&params, &mut cx, &inferred, &prefixes)
+}
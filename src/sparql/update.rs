@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, HashSet};
+
+use tortank::turtle::turtle_doc::TurtleDocError;
+
+use super::{
+    eval::{instantiate, Evaluator, Solution},
+    parser::{GraphPattern, Parser, TermPattern, TriplePattern},
+};
+use crate::{
+    graph::{Graph, Term, Triple},
+    patch::{apply, PatchOperation},
+};
+
+pub enum UpdateOperation {
+    InsertData(Vec<TriplePattern>),
+    DeleteData(Vec<Triple>),
+    Modify {
+        delete: Vec<TriplePattern>,
+        insert: Vec<TriplePattern>,
+        pattern: GraphPattern,
+    },
+    Clear,
+}
+
+/// operations separated by ';', each one can have its own prologue
+pub fn parse_update(
+    input: &str,
+    prefixes: BTreeMap<String, String>,
+) -> Result<Vec<UpdateOperation>, TurtleDocError> {
+    let mut parser = Parser::new(input, prefixes)?;
+    let mut operations = vec![];
+    loop {
+        parser.parse_prologue()?;
+        if parser.peek().is_none() {
+            break;
+        }
+        operations.push(parse_operation(&mut parser)?);
+        if !parser.eat_punct(";") {
+            if parser.peek().is_some() {
+                return Err(parser.error("expected ;"));
+            }
+            break;
+        }
+    }
+    if operations.is_empty() {
+        return Err(parser.error("expected an update operation"));
+    }
+    Ok(operations)
+}
+
+fn parse_operation(parser: &mut Parser) -> Result<UpdateOperation, TurtleDocError> {
+    if parser.eat_keyword("INSERT") {
+        if parser.eat_keyword("DATA") {
+            let template = parser.parse_template()?;
+            if has_variables(&template) {
+                return Err(parser.error("variables are not allowed in INSERT DATA"));
+            }
+            return Ok(UpdateOperation::InsertData(template));
+        }
+        let insert = parser.parse_template()?;
+        parser.expect_keyword("WHERE")?;
+        let pattern = parser.parse_group_graph_pattern()?;
+        Ok(UpdateOperation::Modify {
+            delete: vec![],
+            insert,
+            pattern,
+        })
+    } else if parser.eat_keyword("DELETE") {
+        if parser.eat_keyword("DATA") {
+            let template = parser.parse_template()?;
+            let triples: Option<Vec<Triple>> = template.iter().map(ground).collect();
+            return match triples {
+                Some(triples) => Ok(UpdateOperation::DeleteData(triples)),
+                None => {
+                    Err(parser.error("variables and blank nodes are not allowed in DELETE DATA"))
+                }
+            };
+        }
+        if parser.eat_keyword("WHERE") {
+            let pattern = parser.parse_group_graph_pattern()?;
+            let GraphPattern::Bgp(delete) = &pattern else {
+                return Err(parser.error("DELETE WHERE only accepts triple patterns"));
+            };
+            return Ok(UpdateOperation::Modify {
+                delete: delete.clone(),
+                insert: vec![],
+                pattern,
+            });
+        }
+        let delete = parser.parse_template()?;
+        if delete.iter().any(|t| blank_node(t).is_some()) {
+            return Err(parser.error("blank nodes are not allowed in a DELETE template"));
+        }
+        let insert = if parser.eat_keyword("INSERT") {
+            parser.parse_template()?
+        } else {
+            vec![]
+        };
+        parser.expect_keyword("WHERE")?;
+        let pattern = parser.parse_group_graph_pattern()?;
+        Ok(UpdateOperation::Modify {
+            delete,
+            insert,
+            pattern,
+        })
+    } else if parser.eat_keyword("CLEAR") {
+        parser.eat_keyword("SILENT");
+        if parser.eat_keyword("DEFAULT") || parser.eat_keyword("ALL") {
+            Ok(UpdateOperation::Clear)
+        } else {
+            Err(parser.error("only CLEAR DEFAULT and CLEAR ALL are supported"))
+        }
+    } else {
+        Err(parser.error("expected INSERT, DELETE or CLEAR"))
+    }
+}
+
+fn has_variables(template: &[TriplePattern]) -> bool {
+    template.iter().any(|t| {
+        [&t.subject, &t.predicate, &t.object]
+            .into_iter()
+            .any(|p| matches!(p, TermPattern::Variable(_)))
+    })
+}
+
+fn blank_node(pattern: &TriplePattern) -> Option<&str> {
+    [&pattern.subject, &pattern.object]
+        .into_iter()
+        .find_map(|p| match p {
+            TermPattern::Term(Term::BlankNode(label)) => Some(label.as_str()),
+            _ => None,
+        })
+}
+
+fn ground(pattern: &TriplePattern) -> Option<Triple> {
+    if blank_node(pattern).is_some() {
+        return None;
+    }
+    instantiate(pattern, &Solution::new(), str::to_string)
+}
+
+/// executes the operations in order. blank nodes of an insert template are
+/// fresh for each solution and never collide with a label of the model.
+pub fn execute(
+    mut model: Vec<Triple>,
+    operations: &[UpdateOperation],
+) -> Result<Vec<Triple>, TurtleDocError> {
+    for operation in operations {
+        model = match operation {
+            UpdateOperation::InsertData(template) => {
+                let labels = blank_node_labels(&model);
+                let inserted = template
+                    .iter()
+                    .filter_map(|t| {
+                        instantiate(t, &Solution::new(), |l| fresh_label(l, 0, &labels))
+                    })
+                    .collect();
+                apply(model, vec![PatchOperation::Add(inserted)], false)?
+            }
+            UpdateOperation::DeleteData(triples) => {
+                apply(model, vec![PatchOperation::Delete(triples.clone())], false)?
+            }
+            UpdateOperation::Modify {
+                delete,
+                insert,
+                pattern,
+            } => {
+                let graph = Graph::new(model.iter().cloned());
+                let solutions = Evaluator::new(&graph).eval(pattern, vec![Solution::new()]);
+                let labels = blank_node_labels(&model);
+                let mut deleted = vec![];
+                let mut inserted = vec![];
+                for (row, solution) in solutions.iter().enumerate() {
+                    deleted.extend(
+                        delete
+                            .iter()
+                            .filter_map(|t| instantiate(t, solution, str::to_string)),
+                    );
+                    inserted.extend(insert.iter().filter_map(|t| {
+                        instantiate(t, solution, |l| fresh_label(l, row, &labels))
+                    }));
+                }
+                apply(
+                    model,
+                    vec![
+                        PatchOperation::Delete(deleted),
+                        PatchOperation::Add(inserted),
+                    ],
+                    false,
+                )?
+            }
+            UpdateOperation::Clear => vec![],
+        };
+    }
+    Ok(model)
+}
+
+pub(super) fn blank_node_labels(model: &[Triple]) -> HashSet<String> {
+    model
+        .iter()
+        .flat_map(|t| [&t.subject, &t.object])
+        .filter_map(|t| match t {
+            Term::BlankNode(label) => Some(label.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(super) fn fresh_label(label: &str, row: usize, existing: &HashSet<String>) -> String {
+    let mut fresh = format!("{label}_{row}");
+    while existing.contains(&fresh) {
+        fresh.push('_');
+    }
+    fresh
+}
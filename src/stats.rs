@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use neon::prelude::*;
+
+use crate::{
+    graph::{Term, Triple, RDF_LANG_STRING, RDF_TYPE, XSD_INTEGER, XSD_STRING},
+    obj::{load_triples, make_triples_response, PARAMS_LHS_DATA, PARAMS_LHS_PATH},
+};
+
+const PARAMS_VOID: &str = "void";
+const PARAMS_DATASET_IRI: &str = "datasetIri";
+
+const VOID: &str = "http://rdfs.org/ns/void#";
+
+#[derive(Default)]
+struct PropertyPartition {
+    triples: usize,
+    subjects: HashSet<Term>,
+    objects: HashSet<Term>,
+}
+
+#[derive(Default)]
+struct Stats {
+    triples: usize,
+    subjects: HashSet<Term>,
+    predicates: HashSet<Term>,
+    objects: HashSet<Term>,
+    blank_nodes: HashSet<String>,
+    literals_by_datatype: BTreeMap<String, usize>,
+    literals_by_language: BTreeMap<String, usize>,
+    properties: BTreeMap<String, PropertyPartition>,
+    classes: BTreeMap<String, HashSet<Term>>,
+    namespaces: BTreeSet<String>,
+}
+
+impl Stats {
+    fn new(triples: &[Triple]) -> Stats {
+        let mut stats = Stats::default();
+        for triple in triples {
+            stats.triples += 1;
+            stats.subjects.insert(triple.subject.clone());
+            stats.predicates.insert(triple.predicate.clone());
+            stats.objects.insert(triple.object.clone());
+            for term in [&triple.subject, &triple.predicate, &triple.object] {
+                match term {
+                    Term::BlankNode(label) => {
+                        stats.blank_nodes.insert(label.clone());
+                    }
+                    Term::Iri(iri) => {
+                        if let Some(namespace) = namespace(iri) {
+                            stats.namespaces.insert(namespace.to_string());
+                        }
+                    }
+                    Term::Literal { datatype, lang, .. } => {
+                        let datatype = match (datatype, lang) {
+                            (_, Some(_)) => RDF_LANG_STRING,
+                            (Some(datatype), None) => datatype.as_str(),
+                            (None, None) => XSD_STRING,
+                        };
+                        *stats
+                            .literals_by_datatype
+                            .entry(datatype.to_string())
+                            .or_default() += 1;
+                        if let Some(lang) = lang {
+                            *stats
+                                .literals_by_language
+                                .entry(lang.to_lowercase())
+                                .or_default() += 1;
+                        }
+                    }
+                }
+            }
+            if let Term::Iri(predicate) = &triple.predicate {
+                let partition = stats.properties.entry(predicate.clone()).or_default();
+                partition.triples += 1;
+                partition.subjects.insert(triple.subject.clone());
+                partition.objects.insert(triple.object.clone());
+                if predicate == RDF_TYPE {
+                    if let Term::Iri(class) = &triple.object {
+                        stats
+                            .classes
+                            .entry(class.clone())
+                            .or_default()
+                            .insert(triple.subject.clone());
+                    }
+                }
+            }
+        }
+        stats
+    }
+
+    fn to_void(&self, dataset: Term) -> Vec<Triple> {
+        let mut triples = vec![Triple {
+            subject: dataset.clone(),
+            predicate: Term::Iri(RDF_TYPE.into()),
+            object: Term::Iri(format!("{VOID}Dataset")),
+        }];
+        let mut add = |subject: &Term, property: &str, object: Term| {
+            triples.push(Triple {
+                subject: subject.clone(),
+                predicate: Term::Iri(format!("{VOID}{property}")),
+                object,
+            })
+        };
+        add(&dataset, "triples", integer(self.triples));
+        add(&dataset, "distinctSubjects", integer(self.subjects.len()));
+        add(&dataset, "properties", integer(self.predicates.len()));
+        add(&dataset, "distinctObjects", integer(self.objects.len()));
+        add(&dataset, "classes", integer(self.classes.len()));
+        for namespace in &self.namespaces {
+            add(&dataset, "vocabulary", Term::Iri(namespace.clone()));
+        }
+        for (idx, (property, partition)) in self.properties.iter().enumerate() {
+            let node = Term::BlankNode(format!("propertyPartition{idx}"));
+            add(&dataset, "propertyPartition", node.clone());
+            add(&node, "property", Term::Iri(property.clone()));
+            add(&node, "triples", integer(partition.triples));
+            add(&node, "distinctSubjects", integer(partition.subjects.len()));
+            add(&node, "distinctObjects", integer(partition.objects.len()));
+        }
+        for (idx, (class, entities)) in self.classes.iter().enumerate() {
+            let node = Term::BlankNode(format!("classPartition{idx}"));
+            add(&dataset, "classPartition", node.clone());
+            add(&node, "class", Term::Iri(class.clone()));
+            add(&node, "entities", integer(entities.len()));
+        }
+        triples
+    }
+}
+
+pub fn stats(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let params = cx.argument::<JsObject>(0)?;
+    let void = params
+        .get_opt::<JsBoolean, _, _>(&mut cx, PARAMS_VOID)?
+        .map(|b| b.value(&mut cx))
+        .unwrap_or(false);
+    let dataset = params
+        .get_opt::<JsString, _, _>(&mut cx, PARAMS_DATASET_IRI)?
+        .map(|iri| Term::Iri(iri.value(&mut cx)))
+        .unwrap_or_else(|| Term::BlankNode("dataset".into()));
+    let mut prefixes = BTreeMap::new();
+    let triples = match load_triples(
+        &params,
+        &mut cx,
+        &mut prefixes,
+        PARAMS_LHS_PATH,
+        PARAMS_LHS_DATA,
+    ) {
+        Ok(triples) => triples,
+        Err(e) => return cx.throw_error(e.message),
+    };
+    let stats = Stats::new(&triples);
+    if void {
+        return make_triples_response(&params, &mut cx, &stats.to_void(dataset), &prefixes);
+    }
+
+    let obj = cx.empty_object();
+    for (key, value) in [
+        ("triples", stats.triples),
+        ("distinctSubjects", stats.subjects.len()),
+        ("distinctPredicates", stats.predicates.len()),
+        ("distinctObjects", stats.objects.len()),
+        ("blankNodes", stats.blank_nodes.len()),
+    ] {
+        let value = cx.number(value as f64);
+        obj.set(&mut cx, key, value)?;
+    }
+    let literals = cx.empty_object();
+    for (key, counts) in [
+        ("byDatatype", &stats.literals_by_datatype),
+        ("byLanguage", &stats.literals_by_language),
+    ] {
+        let counts_obj = cx.empty_object();
+        for (k, count) in counts {
+            let count = cx.number(*count as f64);
+            counts_obj.set(&mut cx, k.as_str(), count)?;
+        }
+        literals.set(&mut cx, key, counts_obj)?;
+    }
+    obj.set(&mut cx, "literals", literals)?;
+
+    let properties = JsArray::new(&mut cx, stats.properties.len());
+    for (idx, (property, partition)) in stats.properties.iter().enumerate() {
+        let partition_obj = cx.empty_object();
+        let property = cx.string(property);
+        partition_obj.set(&mut cx, "property", property)?;
+        for (key, value) in [
+            ("triples", partition.triples),
+            ("distinctSubjects", partition.subjects.len()),
+            ("distinctObjects", partition.objects.len()),
+        ] {
+            let value = cx.number(value as f64);
+            partition_obj.set(&mut cx, key, value)?;
+        }
+        properties.set(&mut cx, idx as u32, partition_obj)?;
+    }
+    obj.set(&mut cx, "properties", properties)?;
+
+    let classes = JsArray::new(&mut cx, stats.classes.len());
+    for (idx, (class, entities)) in stats.classes.iter().enumerate() {
+        let partition_obj = cx.empty_object();
+        let class = cx.string(class);
+        partition_obj.set(&mut cx, "class", class)?;
+        let entities = cx.number(entities.len() as f64);
+        partition_obj.set(&mut cx, "entities", entities)?;
+        classes.set(&mut cx, idx as u32, partition_obj)?;
+    }
+    obj.set(&mut cx, "classes", classes)?;
+
+    let namespaces = JsArray::new(&mut cx, stats.namespaces.len());
+    for (idx, namespace) in stats.namespaces.iter().enumerate() {
+        let namespace = cx.string(namespace);
+        namespaces.set(&mut cx, idx as u32, namespace)?;
+    }
+    obj.set(&mut cx, "namespaces", namespaces)?;
+    Ok(obj.upcast())
+}
+
+/// everything up to the last '#' or '/', None if the iri has no local name
+fn namespace(iri: &str) -> Option<&str> {
+    let idx = iri.rfind(['#', '/'])?;
+    (idx + 1 < iri.len()).then(|| &iri[..=idx])
+}
+
+fn integer(value: usize) -> Term {
+    Term::Literal {
+        value: value.to_string(),
+        datatype: Some(XSD_INTEGER.into()),
+        lang: None,
+    }
+}
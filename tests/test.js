@@ -1,4 +1,4 @@
-const { statements, difference, intersection, merge } = require('..');
+const { statements, difference, intersection, merge, symmetricDifference, hash, diff, applyPatch, skolemize, deskolemize, sparql, sparqlUpdate, aggregate, stats, path, closure, cbd, infer, smush, reason, validate, checkLiterals, checkIris } = require('..');
 const assert = require('assert');
 const data = `
           @prefix foaf: <http://foaf.com/>.
@@ -339,6 +339,15 @@ describe("Misc", () => {
       ("http://bittich.be/well-known#"));
 
   });
+
+  it("should write an empty model to a file", () => {
+    assert.equal(true, statements({ lhsData: "", outputFilePath: "/tmp/empty.json" }));
+    assert.equal(true, statements({
+      lhsData: "<http://ex.org/a> <http://ex.org/p> <http://ex.org/b> .",
+      outputFilePath: "/tmp/empty.json",
+      bufSize: 0,
+    }));
+  });
 });
 
 describe("Functions Mapper", () => {
@@ -384,3 +393,1042 @@ describe("Functions Mapper", () => {
 
   });
 });
+
+describe("Hash", () => {
+  it("should produce the same digest for the same model", () => {
+    const lhs = hash({ lhsPath: "../example/modelA.ttl" });
+    const rhs = hash({ lhsData: statements({ lhsPath: "../example/modelA.ttl" }) });
+    assert.equal(64, lhs.length);
+    assert.equal(lhs, rhs);
+    assert.notEqual(lhs, hash({ lhsPath: "../example/modelB.ttl" }));
+  });
+
+  it("should not depend on generated blank node labels", () => {
+    const data = `
+      @prefix foaf: <http://foaf.com/>.
+        [ foaf:name "Alice" ] foaf:knows [ foaf:name "Bob" ] .
+    `;
+    assert.equal(hash({ lhsData: data }), hash({ lhsData: data }));
+    assert.equal(hash({ lhsData: data, hashAlgorithm: "multiset" }),
+      hash({ lhsData: data, hashAlgorithm: "multiset" }));
+  });
+
+  it("should update a multiset hash incrementally", () => {
+    const full = hash({ lhsPath: "../example/modelA.ttl", hashAlgorithm: "multiset" });
+    const eve = `<http://bittich.be/Eve> <http://foaf.com/name> "Eve" .`;
+    const withEve = hash({ lhsData: eve, previousHash: full, hashAlgorithm: "multiset" });
+    assert.notEqual(full, withEve);
+    const merged = merge({ lhsPath: "../example/modelA.ttl", rhsData: eve, outputType: "n3" });
+    assert.equal(withEve, hash({ lhsData: merged, hashAlgorithm: "multiset" }));
+
+    const empty = hash({ lhsData: [], hashAlgorithm: "multiset" });
+    assert.equal(full, hash({
+      lhsData: [], rhsData: eve, previousHash: withEve, hashAlgorithm: "multiset"
+    }));
+    assert.equal("0".repeat(64), empty);
+  });
+});
+
+describe("Diff", () => {
+  it("should return added and removed statements in one pass", () => {
+    const params = {
+      lhsPath: "../example/modelA.ttl",
+      rhsPath: "../example/modelB.ttl",
+    };
+    const { added, removed } = diff(params);
+    assert.deepEqual(difference({ ...params, outputType: "js" }), removed);
+    assert.deepEqual(difference({
+      lhsPath: params.rhsPath, rhsPath: params.lhsPath, outputType: "js"
+    }), added);
+  });
+
+  it("should not report unchanged anonymous nodes", () => {
+    const data = `
+      @prefix foaf: <http://foaf.com/>.
+        [ foaf:name "Alice" ] foaf:knows [ foaf:name "Bob" ] .
+    `;
+    const { added, removed } = diff({ lhsData: data, rhsData: data });
+    assert.deepEqual([], added);
+    assert.deepEqual([], removed);
+  });
+
+  it("should keep the labels of anonymous nodes when others are added", () => {
+    const lhsData = `
+      @prefix foaf: <http://foaf.com/>.
+        [ foaf:name "Alice" ] foaf:knows [ foaf:name "Bob" ] .
+        [ foaf:name "Zed" ] foaf:age 3 .
+    `;
+    const others = ["Carol", "Dan", "Erin", "Finn"].map((name) => `[ foaf:name "${name}" ] .`);
+    const { added, removed } = diff({ lhsData, rhsData: `${lhsData} ${others.join(" ")}` });
+    assert.deepEqual(["Carol", "Dan", "Erin", "Finn"], added.map((t) => t.object.value).sort());
+    assert.deepEqual([], removed);
+  });
+
+  it("should serialize as rdf patch and sparql update", () => {
+    const lhsData = `<http://bittich.be/Eve> <http://foaf.com/name> "Eve" .`;
+    const rhsData = `<http://bittich.be/Eve> <http://foaf.com/name> "Eve"@en .`;
+    assert.equal(`TX .
+D <http://bittich.be/Eve> <http://foaf.com/name> "Eve" .
+A <http://bittich.be/Eve> <http://foaf.com/name> "Eve"@en .
+TC .
+`, diff({ lhsData, rhsData, outputType: "rdf-patch" }));
+    assert.equal(`DELETE DATA {
+  <http://bittich.be/Eve> <http://foaf.com/name> "Eve" .
+} ;
+INSERT DATA {
+  <http://bittich.be/Eve> <http://foaf.com/name> "Eve"@en .
+}
+`, diff({ lhsData, rhsData, outputType: "sparql" }));
+
+    assert.equal(true, diff({ lhsData, rhsData, outputFilePath: "/tmp/diff.patch" }));
+    assert.equal(diff({ lhsData, rhsData, outputType: "rdf-patch" }),
+      require('fs').readFileSync("/tmp/diff.patch", "utf8"));
+    for (const outputType of ["n3", "js", "rdfPatch"]) {
+      assert.throws(() => diff({ lhsData, rhsData, outputType, outputFilePath: "/tmp/diff.patch" }), /cannot write/);
+    }
+  });
+});
+
+describe("Apply Patch", () => {
+  const eve = `<http://bittich.be/Eve> <http://foaf.com/name> "Eve" .`;
+
+  it("should apply a patch made by diff", () => {
+    const params = { lhsPath: "../example/modelA.ttl", rhsPath: "../example/modelC.ttl" };
+    for (const outputType of ["rdf-patch", "sparql", "js"]) {
+      const patch = diff({ ...params, outputType });
+      const patched = applyPatch({ lhsPath: params.lhsPath, patch, outputType: "n3" });
+      const { added, removed } = diff({ lhsData: patched, rhsPath: params.rhsPath });
+      assert.deepEqual([], added);
+      assert.deepEqual([], removed);
+    }
+  });
+
+  it("should apply a sparql data update", () => {
+    const patch = `
+      PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+      DELETE DATA { <mailto:person@example.net> foaf:name "Anne Example-Person" } ;
+      # comment } with a brace
+      INSERT DATA { <mailto:person@example.net> foaf:name "Anne {Example}" . }
+    `;
+    const res = applyPatch({
+      lhsPath: "../example/modelA.ttl", patch, predicate: undefined, outputType: "js"
+    });
+    const names = res.filter((t) => t.predicate.value === "http://xmlns.com/foaf/0.1/name");
+    assert.deepEqual(["Anne {Example}"], names.map((t) => t.object.value));
+  });
+
+  it("should discard aborted transactions", () => {
+    const patch = `TX .\nA ${eve}\nTA .\nTX .\nPA foaf: <http://foaf.com/> .\nA <http://bittich.be/Bob> foaf:name "Bob" .\nTC .`;
+    const res = applyPatch({ lhsData: [], patch });
+    assert.deepEqual(["Bob"], res.map((t) => t.object.value));
+  });
+
+  it("should fail in strict mode when a deleted statement is absent", () => {
+    const patch = { removed: statements({ lhsData: eve }) };
+    assert.deepEqual([], applyPatch({ lhsData: eve, patch, strict: true }));
+    assert.throws(() => applyPatch({ lhsData: [], patch, strict: true }));
+    assert.deepEqual([], applyPatch({ lhsData: [], patch }));
+  });
+
+  it("should apply line by line patches in order", () => {
+    const bob = `<http://bittich.be/Bob> <http://foaf.com/name> "Bob" .`;
+    const patch = [`D ${eve}`, `A ${bob}`, `A ${eve}`, `D ${bob}`, `D ${eve}`, `A ${eve}`].join("\n");
+    assert.deepEqual(["Eve"], applyPatch({ lhsData: eve, patch, strict: true }).map((t) => t.object.value));
+    assert.throws(() => applyPatch({ lhsData: eve, patch: `D ${eve}\nD ${eve}`, strict: true }), /not in the model/);
+  });
+
+  it("should apply a diff removing blank nodes with the default options", () => {
+    const lhsData = `
+      @prefix ex: <http://ex.org/>.
+      @prefix foaf: <http://foaf.com/>.
+      ex:a foaf:name "A" ; foaf:knows [ foaf:name "anon" ] .
+    `;
+    const rhsData = `<http://ex.org/a> <http://foaf.com/name> "A" .`;
+    for (const outputType of ["rdf-patch", "js"]) {
+      const patch = diff({ lhsData, rhsData, outputType });
+      const patched = applyPatch({ lhsData, patch, strict: true, outputType: "n3" });
+      assert.deepEqual({ added: [], removed: [] }, diff({ lhsData: patched, rhsData }));
+    }
+  });
+
+  it("should only relabel blank nodes when needed", () => {
+    const lhsData = `[ <http://foaf.com/name> "Alice" ] <http://foaf.com/knows> [ <http://foaf.com/name> "Bob" ] .`;
+    const rhsData = `${lhsData} ${eve}`;
+    const patch = diff({ lhsData, rhsData, outputType: "rdf-patch" });
+    const relabeled = applyPatch({ lhsData, patch, relabelBlankNodes: true, outputType: "n3" });
+    assert.deepEqual({ added: [], removed: [] }, diff({ lhsData: relabeled, rhsData }));
+    const kept = applyPatch({ lhsData, patch });
+    assert.equal(4, kept.length);
+    assert.ok(kept.every((t) => t.subject.type !== "bnode" || !t.subject.value.startsWith("c14n")));
+  });
+});
+
+describe("N-ary set operations", () => {
+  const inputs = [
+    { path: "../example/modelA.ttl" },
+    { path: "../example/modelB.ttl" },
+    { data: statements({ lhsPath: "../example/modelC.ttl" }) },
+  ];
+
+  it("should compute the symmetric difference of two models", () => {
+    const params = { lhsPath: "../example/modelA.ttl", rhsPath: "../example/modelB.ttl" };
+    const { added, removed } = diff(params);
+    const actual = symmetricDifference(params);
+    assert.equal(added.length + removed.length, actual.length);
+    assert.deepEqual([], intersection({ lhsData: actual, rhsPath: "../example/modelA.ttl", outputType: "js" })
+      .filter((t) => !removed.some((r) => JSON.stringify(r) === JSON.stringify(t))));
+  });
+
+  it("should accept an arbitrary number of inputs", () => {
+    const union = merge({ inputs });
+    const pairwise = merge({
+      lhsData: merge({ lhsPath: inputs[0].path, rhsPath: inputs[1].path }),
+      rhsData: inputs[2].data,
+    });
+    assert.equal(pairwise.length, union.length);
+
+    const common = intersection({ inputs });
+    assert.deepEqual(intersection({
+      lhsData: intersection({ lhsPath: inputs[0].path, rhsPath: inputs[1].path }),
+      rhsData: inputs[2].data,
+    }), common);
+
+    assert.deepEqual(difference({
+      lhsData: difference({ lhsPath: inputs[0].path, rhsPath: inputs[1].path }),
+      rhsData: inputs[2].data,
+    }), difference({ inputs }));
+
+    assert.equal(0, symmetricDifference({ inputs: [inputs[0], inputs[0]] }).length);
+    const sorted = (triples) => triples.map((t) => JSON.stringify(t)).sort();
+    assert.deepEqual(sorted(statements({ lhsPath: inputs[0].path })),
+      sorted(symmetricDifference({ inputs: [inputs[0], inputs[1], inputs[1]] })));
+    assert.throws(() => merge({ inputs: [] }));
+  });
+});
+
+describe("Blank node merge", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    _:b0 foaf:name "Alice" .
+  `;
+  const rhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    _:b0 foaf:name "Bob" .
+  `;
+
+  it("should rename blank nodes per input by default", () => {
+    const res = merge({ lhsData, rhsData });
+    assert.equal(2, res.length);
+    assert.notEqual(res[0].subject.value, res[1].subject.value);
+    assert.equal("b0", res[0].subject.value);
+
+    const nary = merge({ inputs: [{ data: lhsData }, { data: rhsData }, { data: rhsData }] });
+    assert.equal(3, new Set(nary.map((t) => t.subject.value)).size);
+  });
+
+  it("should keep labels with blankNodeMerge union", () => {
+    const res = merge({ lhsData, rhsData, blankNodeMerge: "union" });
+    assert.equal(2, res.length);
+    assert.equal(res[0].subject.value, res[1].subject.value);
+    assert.throws(() => merge({ lhsData, rhsData, blankNodeMerge: "unknown" }));
+  });
+});
+
+describe("Skolemization", () => {
+  const data = `
+    @prefix foaf: <http://foaf.com/>.
+    [ foaf:name "Alice" ] foaf:knows [ foaf:name "Bob" ] .
+  `;
+
+  it("should replace blank nodes by well known iris and back", () => {
+    const skolemized = skolemize({ lhsData: data, skolemBase: "http://bittich.be/" });
+    assert.equal(3, skolemized.length);
+    for (const t of skolemized) {
+      assert.equal("uri", t.subject.type);
+      assert.ok(t.subject.value.startsWith("http://bittich.be/.well-known/genid/"));
+    }
+    // works with rdf/json input as well
+    const deskolemized = deskolemize({ lhsData: skolemized, skolemBase: "http://bittich.be" });
+    assert.deepEqual(statements({ lhsData: deskolemized }), deskolemized);
+    assert.ok(deskolemized.every((t) => t.subject.type === "bnode"));
+    assert.deepEqual(deskolemized, deskolemize({ lhsData: skolemized }));
+    assert.deepEqual(skolemized, deskolemize({ lhsData: skolemized, skolemBase: "http://other.be" }));
+    assert.throws(() => skolemize({ lhsData: data }));
+  });
+});
+
+describe("SPARQL", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    @prefix ex: <http://example.org/>.
+    ex:alice a foaf:Person ; foaf:name "Alice"@en ; foaf:age 30 ; foaf:knows ex:bob .
+    ex:bob a foaf:Person ; foaf:name "Bob" ; foaf:age 25 .
+    ex:carol a foaf:Person ; foaf:name "Carol" ; foaf:age 41 .
+  `;
+  const prefix = "PREFIX foaf: <http://foaf.com/> ";
+
+  it("should evaluate select queries", () => {
+    const res = sparql({
+      lhsData,
+      query: prefix + `SELECT ?name ?friend WHERE {
+        ?s a foaf:Person ; foaf:name ?name ; foaf:age ?age .
+        OPTIONAL { ?s foaf:knows ?friend }
+        FILTER(?age > 26)
+      } ORDER BY DESC(?age)`,
+    });
+    assert.deepEqual(["name", "friend"], res.head.vars);
+    assert.equal(2, res.results.bindings.length);
+    assert.equal("Carol", res.results.bindings[0].name.value);
+    assert.equal(undefined, res.results.bindings[0].friend);
+    assert.deepEqual({ value: "Alice", type: "literal", lang: "en" }, res.results.bindings[1].name);
+    assert.equal("http://example.org/bob", res.results.bindings[1].friend.value);
+
+    const union = sparql({
+      lhsData,
+      query: prefix + `SELECT DISTINCT ?name WHERE {
+        { ?s foaf:name ?name FILTER(lang(?name) = "en") } UNION { ?s foaf:name ?name FILTER regex(?name, "^b", "i") }
+      } ORDER BY ?name LIMIT 1 OFFSET 1`,
+    });
+    assert.deepEqual([{ name: { value: "Bob", type: "literal", datatype: "http://www.w3.org/2001/XMLSchema#string" } }],
+      union.results.bindings);
+  });
+
+  it("should evaluate ask and construct queries", () => {
+    assert.equal(true, sparql({ lhsData, query: prefix + "ASK { ?s foaf:age 41 }" }));
+    assert.equal(false, sparql({ lhsData, query: prefix + "ASK { ?s foaf:knows ?o FILTER(!bound(?o)) }" }));
+    const res = sparql({
+      lhsData,
+      query: "CONSTRUCT { ?o foaf:knownBy ?s } WHERE { ?s foaf:knows ?o }",
+      extraPrefixes: { foaf: "http://foaf.com/" },
+    });
+    assert.deepEqual([{
+      subject: { value: "http://example.org/bob", type: "uri" },
+      predicate: { value: "http://foaf.com/knownBy", type: "uri" },
+      object: { value: "http://example.org/alice", type: "uri" },
+    }], res);
+    assert.throws(() => sparql({ lhsData, query: "SELECT ?s WHERE { ?s foaf:age ?age }" }));
+  });
+
+  it("should compute integers and decimals exactly", () => {
+    const data = `
+      @prefix ex: <http://example.org/>.
+      ex:a ex:v 9007199254740993 .
+      ex:b ex:v 9007199254740992 .
+      ex:c ex:v -0.05 .
+    `;
+    const select = (filter) => sparql({
+      lhsData: data,
+      query: `SELECT ?s WHERE { ?s <http://example.org/v> ?v FILTER(${filter}) }`,
+    }).results.bindings.map((b) => b.s.value);
+    assert.deepEqual(["http://example.org/b"], select("?v = 9007199254740992"));
+    assert.deepEqual(["http://example.org/a"], select("?v + 1 = 9007199254740994"));
+    assert.deepEqual(["http://example.org/a"], select(`str(?v * 2) = "18014398509481986"`));
+    assert.deepEqual(["http://example.org/c"], select(`str(?v - 1) = "-1.05"`));
+    assert.deepEqual(["http://example.org/c"], select("0.1 + 0.2 = 0.3 && ?v < 0"));
+  });
+
+  it("should compare dateTimes across timezones", () => {
+    const data = `
+      @prefix ex: <http://example.org/>.
+      @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+      ex:a ex:t "2024-01-01T00:00:00+02:00"^^xsd:dateTime .
+      ex:b ex:t "2023-12-31T23:00:00Z"^^xsd:dateTime .
+      ex:c ex:t "2023-12-31T22:30:00"^^xsd:dateTime .
+    `;
+    const select = (query) => sparql({ lhsData: data, query }).results.bindings.map((b) => b.s.value);
+    assert.deepEqual(["http://example.org/a"], select(`SELECT ?s WHERE {
+      ?s <http://example.org/t> ?t FILTER(?t < "2023-12-31T23:00:00Z"^^<http://www.w3.org/2001/XMLSchema#dateTime>)
+    }`));
+    // a local time is not comparable to a timezoned one
+    assert.deepEqual([], select(`SELECT ?s WHERE {
+      ?s <http://example.org/t> ?t FILTER(?t = "2023-12-31T22:30:00Z"^^<http://www.w3.org/2001/XMLSchema#dateTime>)
+    }`));
+    assert.deepEqual(["http://example.org/a", "http://example.org/b"], select(`SELECT ?s WHERE {
+      ?s <http://example.org/t> ?t FILTER(?s != <http://example.org/c>)
+    } ORDER BY ?t`));
+  });
+
+  it("should order mixed terms by kind then by value", () => {
+    const data = `
+      @prefix ex: <http://example.org/>.
+      @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+      ex:s ex:p "b"@en, 2.5, "a", true, "NaN"^^xsd:double, 1, ex:o, _:b1, "2020-01-01"^^xsd:date, "10"^^xsd:int .
+    `;
+    const values = (order) => sparql({
+      lhsData: data,
+      query: `SELECT ?o WHERE { ?s <http://example.org/p> ?o } ORDER BY ${order}(?o)`,
+    }).results.bindings.map((b) => b.o.value);
+    const asc = values("ASC");
+    assert.deepEqual(["http://example.org/o", "1", "2.5", "10", "NaN", "a", "b", "true", "2020-01-01"], asc.slice(1));
+    assert.deepEqual([...asc].reverse(), values("DESC"));
+  });
+});
+
+describe("SPARQL Update", () => {
+  const lhsData = `
+    @prefix ex: <http://example.org/>.
+    ex:alice ex:name "Alice" ; ex:age 30 .
+    ex:bob ex:name "Bob" ; ex:age 25 .
+  `;
+  const extraPrefixes = { ex: "http://example.org/" };
+
+  it("should execute data and pattern updates in order", () => {
+    const res = sparqlUpdate({
+      lhsData,
+      extraPrefixes,
+      update: `
+        DELETE { ?s ex:age ?age } INSERT { ?s ex:adult true } WHERE { ?s ex:age ?age FILTER(?age >= 30) } ;
+        INSERT DATA { ex:carol ex:name "Carol" } ;
+        DELETE DATA { ex:bob ex:age 25 } ;
+        DELETE WHERE { ?s ex:name "Bob" }
+      `,
+    });
+    assert.deepEqual([
+      "http://example.org/alice http://example.org/name Alice",
+      "http://example.org/alice http://example.org/adult true",
+      "http://example.org/carol http://example.org/name Carol",
+    ], res.map((t) => `${t.subject.value} ${t.predicate.value} ${t.object.value}`));
+    assert.equal(0, sparqlUpdate({ lhsData, update: "CLEAR DEFAULT" }).length);
+    assert.throws(() => sparqlUpdate({ lhsData, extraPrefixes, update: "DELETE DATA { ?s ex:age 25 }" }));
+  });
+
+  it("should write the result in place", () => {
+    const fs = require('fs');
+    const lhsPath = "/tmp/sparql-update.ttl";
+    fs.writeFileSync(lhsPath, lhsData);
+    assert.equal(true, sparqlUpdate({ lhsPath, extraPrefixes, inPlace: true, update: "DELETE WHERE { ex:bob ?p ?o }" }));
+    assert.equal(2, statements({ lhsPath }).length);
+    assert.deepEqual(["sparql-update.ttl"], fs.readdirSync("/tmp").filter((f) => f.startsWith("sparql-update")));
+    assert.throws(() => sparqlUpdate({ lhsData, inPlace: true, update: "CLEAR ALL" }));
+  });
+});
+
+describe("Aggregate", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    @prefix ex: <http://example.org/>.
+    ex:alice foaf:knows ex:bob, ex:carol ; foaf:age 30 ; foaf:name "Alice"@en .
+    ex:bob foaf:knows ex:carol ; foaf:age 25 ; foaf:name "Bob"@fr .
+    ex:carol foaf:age 41.5 .
+  `;
+
+  it("should count filtered statements per group", () => {
+    const res = aggregate({ lhsData, predicate: "foaf:knows", groupBy: "subject" });
+    assert.deepEqual([
+      { group: { subject: { value: "http://example.org/alice", type: "uri" } }, count: 2, distinct: { subjects: 1, predicates: 1, objects: 2 } },
+      { group: { subject: { value: "http://example.org/bob", type: "uri" } }, count: 1, distinct: { subjects: 1, predicates: 1, objects: 1 } },
+    ], res);
+    const languages = aggregate({ lhsData, predicate: "foaf:name", groupBy: ["language"] });
+    assert.deepEqual(["en", "fr"], languages.map((g) => g.group.language));
+    assert.throws(() => aggregate({ lhsData, groupBy: "graph" }));
+  });
+
+  it("should compute min, max and sum over literals", () => {
+    const [res] = aggregate({ lhsData, predicate: "foaf:age" });
+    assert.equal(3, res.count);
+    assert.equal("25", res.min.value);
+    assert.equal("41.5", res.max.value);
+    assert.equal(96.5, res.sum);
+  });
+});
+
+describe("Stats", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    @prefix ex: <http://example.org/>.
+    ex:alice a foaf:Person ; foaf:knows ex:bob, [ foaf:name "Carol" ] ; foaf:name "Alice"@en .
+    ex:bob a foaf:Person ; foaf:name "Bob" .
+  `;
+
+  it("should describe the dataset", () => {
+    const res = stats({ lhsData });
+    assert.equal(7, res.triples);
+    assert.equal(3, res.distinctSubjects);
+    assert.equal(3, res.distinctPredicates);
+    assert.equal(1, res.blankNodes);
+    assert.deepEqual({
+      byDatatype: {
+        "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString": 1,
+        "http://www.w3.org/2001/XMLSchema#string": 2,
+      },
+      byLanguage: { en: 1 },
+    }, res.literals);
+    assert.deepEqual({ property: "http://foaf.com/name", triples: 3, distinctSubjects: 3, distinctObjects: 3 },
+      res.properties.find((p) => p.property === "http://foaf.com/name"));
+    assert.deepEqual([{ class: "http://foaf.com/Person", entities: 2 }], res.classes);
+    assert.deepEqual(["http://example.org/", "http://foaf.com/", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"],
+      res.namespaces);
+  });
+
+  it("should output a void description", () => {
+    const res = stats({ lhsData, void: true, datasetIri: "http://example.org/dataset" });
+    const triples = res.find((t) => t.predicate.value === "http://rdfs.org/ns/void#triples"
+      && t.subject.value === "http://example.org/dataset");
+    assert.equal("7", triples.object.value);
+    assert.equal(3, res.filter((t) => t.predicate.value === "http://rdfs.org/ns/void#propertyPartition").length);
+  });
+});
+
+describe("Statement filters", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    <http://bittich.be/alice> foaf:name "Alice" ; foaf:knows <http://other.be/bob> .
+    <http://other.be/bob> foaf:name "Bob" .
+  `;
+  const values = (res) => res.map((t) => `${t.subject.value} ${t.object.value}`);
+
+  it("should filter with prefix, contains and regex", () => {
+    assert.deepEqual(["http://bittich.be/alice Alice", "http://bittich.be/alice http://other.be/bob"],
+      values(statements({ lhsData, subject: { prefix: "http://bittich.be/" } })));
+    assert.deepEqual(["http://other.be/bob Bob"],
+      values(statements({ lhsData, predicate: "foaf:name", object: { contains: "ob" } })));
+    assert.deepEqual(["http://bittich.be/alice Alice"],
+      values(statements({ lhsData, object: { regex: "^a", flags: "i" } })));
+    assert.throws(() => statements({ lhsData, object: { regex: "(" } }));
+    assert.throws(() => statements({ lhsData, object: { startsWith: "a" } }));
+  });
+});
+
+describe("Language and datatype filters", () => {
+  const lhsData = `
+    @prefix skos: <http://www.w3.org/2004/02/skos/core#>.
+    @prefix x: <http://www.w3.org/2001/XMLSchema#>.
+    <http://ex.org/a> skos:prefLabel "chat"@fr, "cat"@en-GB, "cat"@en, "Katze"@de-Latn-CH ;
+      skos:date "2020-01-01"^^x:date ; skos:n 4 .
+  `;
+  const values = (res) => res.map((t) => `${t.object.value}|${t.object.lang || t.object.datatype}`);
+
+  it("should filter by language range", () => {
+    assert.deepEqual(["cat|en-GB", "cat|en"], values(statements({ lhsData, lang: "en-*" })));
+    assert.deepEqual(["chat|fr"], values(statements({ lhsData, lang: "FR" })));
+    assert.deepEqual(["Katze|de-Latn-CH"], values(statements({ lhsData, lang: "*-CH" })));
+  });
+
+  it("should filter by datatype", () => {
+    assert.deepEqual(["2020-01-01|http://www.w3.org/2001/XMLSchema#date"], values(statements({ lhsData, datatype: "x:date" })));
+    assert.deepEqual(["4|http://www.w3.org/2001/XMLSchema#integer"], values(statements({ lhsData, datatype: "xsd:integer" })));
+    assert.equal(4, statements({ lhsData, datatype: "<http://www.w3.org/1999/02/22-rdf-syntax-ns#langString>" }).length);
+  });
+
+  it("should only resolve datatypes with the declared prefixes", () => {
+    const lhsData = `
+      @prefix x: <http://www.w3.org/2001/XMLSchema#>.
+      # @prefix x: <http://wrong.org/>.
+      <http://ex.org/a> <http://ex.org/p> "@prefix x: <http://wrong.org/> ."@en, "2020-01-01"^^x:date .
+    `;
+    assert.equal(1, statements({ lhsData, datatype: "x:date" }).length);
+    assert.equal(1, statements({ lhsData, subject: ["<http://ex.org/a>"], datatype: "x:date" }).length);
+  });
+});
+
+describe("Set filters", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    <http://ex.org/a> foaf:name "Alice" ; foaf:lastName "Doe" ; foaf:age 42 ; foaf:knows <http://ex.org/b> .
+    <http://ex.org/b> foaf:name "Bob" .
+  `;
+  const values = (res) => res.map((t) => t.object.value);
+
+  it("should filter by a set of values", () => {
+    assert.deepEqual(["Alice", "Doe", "Bob"], values(statements({ lhsData, predicate: ["foaf:name", "foaf:lastName"] })));
+    assert.deepEqual(["Doe", "42"], values(statements({ lhsData, object: ['"Doe"', "42", '"42"'] })));
+    assert.deepEqual(["Bob"], values(statements({ lhsData, subject: ["http://ex.org/b", "<http://ex.org/c>"] })));
+  });
+
+  it("should exclude values", () => {
+    assert.deepEqual(["Alice", "Bob"], values(statements({ lhsData, predicate: "foaf:name", object: { not: { regex: "^Z" } } })));
+    assert.deepEqual(["Doe", "42", "http://ex.org/b"], values(statements({ lhsData, predicate: { not: "foaf:name" } })));
+    assert.deepEqual(["42", "http://ex.org/b"], values(statements({ lhsData, subject: "<http://ex.org/a>", predicate: { not: ["foaf:name", "foaf:lastName"] } })));
+    assert.throws(() => statements({ lhsData, predicate: ["unknown:name"] }));
+  });
+});
+
+describe("Property paths", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    @prefix ex: <http://ex.org/>.
+    ex:a foaf:knows ex:b ; foaf:name "A" .
+    ex:b foaf:knows ex:c ; foaf:name "B" .
+    ex:c foaf:knows ex:a, ex:d .
+    ex:d foaf:member ex:g .
+  `;
+  const values = (res) => res.map((n) => n.value);
+
+  it("should return the reached nodes", () => {
+    assert.deepEqual(["B"], values(path({ lhsData, start: "ex:a", path: "foaf:knows/foaf:name" })));
+    assert.deepEqual(["http://ex.org/b", "http://ex.org/c", "http://ex.org/a", "http://ex.org/d"],
+      values(path({ lhsData, start: "ex:a", path: "foaf:knows+" })));
+    assert.deepEqual(["http://ex.org/d", "http://ex.org/c", "http://ex.org/b", "http://ex.org/a"],
+      values(path({ lhsData, start: "ex:g", path: "^foaf:member/(^foaf:knows)*" })));
+    assert.deepEqual(["http://ex.org/b", "B"], values(path({ lhsData, start: "http://ex.org/b", path: "(foaf:knows/foaf:member)?|foaf:name" })));
+    assert.throws(() => path({ lhsData, start: "ex:a", path: "foaf:knows/" }));
+  });
+
+  it("should return the traversed triples", () => {
+    const res = path({ lhsData, start: "ex:a", path: "foaf:knows*/foaf:member", triples: true });
+    assert.deepEqual(["a b", "b c", "c a", "c d", "d g"],
+      res.map((t) => `${t.subject.value.slice(-1)} ${t.object.value.slice(-1)}`));
+  });
+});
+
+describe("Closure", () => {
+  const lhsData = `
+    @prefix skos: <http://www.w3.org/2004/02/skos/core#>.
+    @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+    @prefix ex: <http://ex.org/>.
+    ex:Root skos:narrower ex:A, ex:B .
+    ex:A skos:narrower ex:A1 .
+    ex:Other skos:narrower ex:O1 .
+    ex:Cat rdfs:subClassOf ex:Mammal .
+    ex:Mammal rdfs:subClassOf ex:Animal .
+    ex:Animal rdfs:subClassOf ex:Cat .
+  `;
+  const values = (res) => res.map((t) => `${t.subject.value.slice(14)} ${t.object.value.slice(14)}`);
+
+  it("should extract the subgraph reachable from the roots", () => {
+    assert.deepEqual(["Root A", "Root B", "A A1"], values(closure({ lhsData, predicates: "skos:narrower", roots: "ex:Root" })));
+    assert.equal(4, closure({ lhsData, predicates: ["skos:narrower"] }).length);
+    assert.throws(() => closure({ lhsData }));
+  });
+
+  it("should materialize the closure", () => {
+    assert.deepEqual(["Root A", "Root B", "A A1", "Root A1"],
+      values(closure({ lhsData, predicates: "skos:narrower", roots: ["ex:Root"], materialize: true })));
+    const res = closure({ lhsData, predicates: "rdfs:subClassOf", materialize: true });
+    assert.equal(9, res.length);
+    assert.ok(values(res).includes("Cat Cat"));
+  });
+});
+
+describe("Concise Bounded Description", () => {
+  const lhsData = `
+    @prefix foaf: <http://foaf.com/>.
+    @prefix ex: <http://ex.org/>.
+    ex:alice foaf:name "Alice" ; foaf:knows [ foaf:name "Bob" ; foaf:address [ foaf:city "Brussels" ] ], ex:carol .
+    ex:carol foaf:name "Carol" .
+    [ foaf:member ex:alice ; foaf:name "Club" ] .
+  `;
+
+  it("should describe a resource and its blank nodes", () => {
+    const res = cbd({ lhsData, subject: "ex:alice" });
+    assert.equal(6, res.length);
+    assert.ok(res.some((t) => t.object.value === "Brussels"));
+    assert.ok(!res.some((t) => t.object.value === "Carol"));
+    assert.equal(5, cbd({ lhsData, subject: "ex:alice", maxDepth: 1 }).length);
+    assert.equal(0, cbd({ lhsData, subject: "<http://ex.org/unknown>" }).length);
+  });
+
+  it("should include incoming statements when symmetric", () => {
+    const res = cbd({ lhsData, subject: "ex:alice", symmetric: true, maxDepth: 1 });
+    assert.equal(7, res.length);
+    assert.ok(res.some((t) => t.object.value === "Club"));
+    assert.equal(2, cbd({ lhsData, subject: "ex:carol", symmetric: true, maxDepth: 0 }).length);
+  });
+});
+
+describe("RDFS entailment", () => {
+  const rhsData = `
+    @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+    @prefix ex: <http://ex.org/>.
+    ex:Cat rdfs:subClassOf ex:Mammal .
+    ex:Mammal rdfs:subClassOf ex:Animal .
+    ex:hasMother rdfs:subPropertyOf ex:hasParent .
+    ex:hasParent rdfs:domain ex:Animal ; rdfs:range ex:Animal .
+    ex:name rdfs:range ex:Animal .
+  `;
+  const lhsData = `
+    @prefix ex: <http://ex.org/>.
+    ex:tom a ex:Cat ; ex:hasMother ex:molly ; ex:name "Tom" .
+  `;
+  const local = (node) => node.value.replace(/^.*[/#]/, "");
+  const values = (res) => res.map((t) => `${local(t.subject)} ${local(t.predicate)} ${local(t.object)}`).sort();
+
+  it("should infer the rdfs closure with an ontology", () => {
+    assert.deepEqual(["Cat subClassOf Animal", "molly type Animal", "tom hasParent molly", "tom type Animal", "tom type Mammal"],
+      values(infer({ lhsData, rhsData, inferredOnly: true })));
+    assert.equal(8, infer({ lhsData, rhsData }).length);
+    assert.equal(0, infer({ lhsData, inferredOnly: true }).length);
+  });
+
+  it("should filter entailed statements", () => {
+    assert.deepEqual(["tom type Animal", "tom type Cat", "tom type Mammal"],
+      values(statements({ lhsData, rhsData, entailment: "rdfs", subject: "<http://ex.org/tom>", predicate: "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>" })));
+    assert.throws(() => statements({ lhsData, entailment: "owl" }));
+  });
+
+  it("should resolve prefixed filters on entailed statements", () => {
+    assert.deepEqual(["tom type Animal", "tom type Cat", "tom type Mammal"],
+      values(statements({ lhsData, rhsData, entailment: "rdfs", subject: "ex:tom", predicate: "rdf:type", extraPrefixes: { rdf: "http://www.w3.org/1999/02/22-rdf-syntax-ns#" } })));
+    assert.deepEqual(["molly type Animal"], values(statements({ lhsData, rhsData, entailment: "rdfs", subject: "ex:molly" })));
+  });
+});
+
+describe("Smushing", () => {
+  const lhsData = `
+    @prefix owl: <http://www.w3.org/2002/07/owl#>.
+    @prefix foaf: <http://foaf.com/>.
+    <http://b.org/alice> owl:sameAs <http://a.org/alice> ; foaf:name "Alice" .
+    <http://c.org/alice> owl:sameAs <http://b.org/alice> ; foaf:knows <http://b.org/bob> .
+    <http://a.org/alice> foaf:name "Alice" .
+  `;
+  const values = (res) => res.map((t) => `${t.subject.value} ${t.predicate.value.replace(/^.*[/#]/, "")} ${t.object.value}`);
+
+  it("should rewrite statements to the smallest iri", () => {
+    assert.deepEqual(["http://a.org/alice name Alice", "http://a.org/alice knows http://b.org/bob"],
+      values(smush({ lhsData })));
+  });
+
+  it("should prefer a namespace and keep the links", () => {
+    assert.deepEqual([
+      "http://c.org/alice name Alice",
+      "http://c.org/alice knows http://b.org/bob",
+      "http://c.org/alice sameAs http://a.org/alice",
+      "http://c.org/alice sameAs http://b.org/alice",
+    ], values(smush({ lhsData, preferredNamespaces: ["http://d.org/", "http://c.org/"], keepSameAs: true })));
+  });
+});
+
+describe("N3 rules", () => {
+  const lhsData = `
+    @prefix : <http://ex.org/>.
+    :a :parent :b . :b :parent :c . :c :parent :d .
+  `;
+  const rulesData = `
+    @prefix : <http://ex.org/>.
+    { ?x :parent ?y } => { ?x :ancestor ?y } .
+    { ?x :ancestor ?y . ?y :parent ?z } => { ?x :ancestor ?z } .
+    { ?x :parent ?y . ?y :parent ?z } => { ?x :grandparent ?z } .
+  `;
+  const values = (res) => res.map((t) => `${t.subject.value.slice(-1)} ${t.predicate.value.slice(14)} ${t.object.value.slice(-1)}`).sort();
+
+  it("should apply the rules until fixpoint", () => {
+    const res = reason({ lhsData, rulesData });
+    assert.deepEqual(["a ancestor b", "a ancestor c", "a ancestor d", "a grandparent c",
+      "b ancestor c", "b ancestor d", "b grandparent d", "c ancestor d"], values(res));
+  });
+
+  it("should stop at the iteration limit", () => {
+    assert.throws(() => reason({ lhsData, rulesData, maxIterations: 2 }), /no fixpoint after 2 iterations/);
+    const infinite = `PREFIX : <http://ex.org/>
+      { ?x :parent ?y } => { ?y :parent [ :name "fresh" ] } .`;
+    assert.throws(() => reason({ lhsData, rulesData: infinite }), /no fixpoint/);
+    assert.throws(() => reason({ lhsData, rulesData: "{ ?x ?p ?o } { ?x ?p ?o }" }));
+  });
+});
+
+describe("SHACL validation", () => {
+  const rhsData = `
+    @prefix sh: <http://www.w3.org/ns/shacl#>.
+    @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+    @prefix ex: <http://ex.org/>.
+    ex:PersonShape a sh:NodeShape ;
+      sh:targetClass ex:Person ;
+      sh:property [ sh:path ex:name ; sh:minCount 1 ; sh:maxCount 1 ; sh:datatype xsd:string ; sh:pattern "^[A-Z]" ] ;
+      sh:property [ sh:path ex:age ; sh:datatype xsd:integer ; sh:minInclusive 0 ; sh:maxExclusive 150 ] ;
+      sh:property [ sh:path ex:gender ; sh:in ( "f" "m" "x" ) ] ;
+      sh:property [ sh:path ex:knows ; sh:class ex:Person ; sh:nodeKind sh:IRI ] ;
+      sh:property [ sh:path ( ex:address ex:city ) ; sh:node ex:CityShape ; sh:maxCount 1 ] .
+    ex:CityShape a sh:NodeShape ; sh:property [ sh:path ex:label ; sh:minCount 1 ] .
+  `;
+  const valid = `
+    @prefix ex: <http://ex.org/>.
+    ex:Student a <http://www.w3.org/2000/01/rdf-schema#Class> ; <http://www.w3.org/2000/01/rdf-schema#subClassOf> ex:Person .
+    ex:alice a ex:Person ; ex:name "Alice" ; ex:age 30 ; ex:gender "f" ; ex:knows ex:bob ;
+      ex:address [ ex:city ex:brussels ] .
+    ex:bob a ex:Student ; ex:name "Bob" .
+    ex:brussels ex:label "Brussels" .
+  `;
+  const invalid = `
+    @prefix ex: <http://ex.org/>.
+    ex:alice a ex:Person ; ex:name "alice", "Alice" ; ex:age 150, "x" ; ex:gender "u" ; ex:knows ex:carol, [ a ex:Person ; ex:name "Anon" ] ;
+      ex:address [ ex:city ex:gent ] .
+    ex:carol ex:name "Carol" .
+  `;
+  const conforms = (report) => report.find((t) => t.predicate.value === "http://www.w3.org/ns/shacl#conforms").object.value;
+  const components = (report) => report
+    .filter((t) => t.predicate.value === "http://www.w3.org/ns/shacl#sourceConstraintComponent")
+    .map((t) => t.object.value.replace("http://www.w3.org/ns/shacl#", ""))
+    .sort();
+
+  it("should validate a conforming data graph", () => {
+    const report = validate({ lhsData: valid, rhsData });
+    assert.equal("true", conforms(report));
+    assert.deepEqual([], components(report));
+  });
+
+  it("should report violations", () => {
+    const report = validate({ lhsData: invalid, rhsData });
+    assert.equal("false", conforms(report));
+    assert.deepEqual([
+      "ClassConstraintComponent",
+      "DatatypeConstraintComponent",
+      "InConstraintComponent",
+      "MaxCountConstraintComponent",
+      "MaxExclusiveConstraintComponent",
+      "MaxExclusiveConstraintComponent",
+      "MinInclusiveConstraintComponent",
+      "NodeConstraintComponent",
+      "NodeKindConstraintComponent",
+      "PatternConstraintComponent",
+    ], components(report));
+    const n3 = validate({ lhsData: invalid, rhsData, outputType: "n3" });
+    assert.ok(n3.includes("<http://www.w3.org/ns/shacl#ValidationResult>"));
+  });
+});
+
+describe("Literal validation", () => {
+  const data = `
+    @prefix ex: <http://ex.org/>.
+    @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+    ex:a ex:age "42"^^xsd:integer ; ex:born "2020-02-29"^^xsd:date ; ex:label "hello"@en .
+    ex:b ex:age "forty"^^xsd:int ; ex:born "2021-02-29"^^xsd:date ; ex:small "300"^^xsd:byte .
+    ex:c ex:at "2020-01-01T10:00:00+15:00"^^xsd:dateTime ; ex:flag "abc"^^xsd:hexBinary ; ex:custom "x"^^ex:type .
+  `;
+
+  it("should report invalid lexical forms", () => {
+    const invalid = checkLiterals({ lhsData: data });
+    assert.deepEqual([
+      "http://ex.org/age",
+      "http://ex.org/at",
+      "http://ex.org/born",
+      "http://ex.org/flag",
+      "http://ex.org/small",
+    ], invalid.map((i) => i.statement.predicate.value).sort());
+    const age = invalid.find((i) => i.statement.predicate.value === "http://ex.org/age");
+    assert.equal("forty", age.statement.object.value);
+    assert.equal('invalid lexical form "forty" for xsd:int', age.message);
+    assert.equal("number", typeof age.position);
+  });
+
+  it("should accept valid literals", () => {
+    assert.deepEqual([], checkLiterals({ lhsData: `<http://ex.org/a> <http://ex.org/p> "1.5E3"^^<http://www.w3.org/2001/XMLSchema#double>, "P1DT2H"^^<http://www.w3.org/2001/XMLSchema#duration>, "24:00:00"^^<http://www.w3.org/2001/XMLSchema#time> .` }));
+  });
+
+  it("should reject invalid literals while parsing when validateLiterals is set", () => {
+    assert.throws(() => statements({ lhsData: data, validateLiterals: true }), /forty/);
+    assert.equal(9, statements({ lhsData: data }).length);
+  });
+});
+
+describe("Literal canonicalization", () => {
+  const xsd = "http://www.w3.org/2001/XMLSchema#";
+  const literal = (value, datatype) => ({
+    subject: { type: "uri", value: "http://ex.org/a" },
+    predicate: { type: "uri", value: "http://ex.org/p" },
+    object: { type: "literal", value, datatype: xsd + datatype },
+  });
+
+  it("should rewrite literals to their canonical form", () => {
+    const values = statements({
+      lhsData: [
+        literal("1", "boolean"),
+        literal("007", "int"),
+        literal("1500", "float"),
+        literal("2020-12-31T23:30:00-01:00", "dateTime"),
+        literal("PT36H", "dayTimeDuration"),
+        literal("ab", "hexBinary"),
+        literal("1e39", "float"),
+        literal("-1e39", "float"),
+      ],
+      canonicalizeLiterals: true,
+    }).map((t) => t.object.value);
+    assert.deepEqual(["true", "7", "1.5E3", "2021-01-01T00:30:00Z", "P1DT12H", "AB", "INF", "-INF"], values);
+  });
+
+  it("should match value-equal literals in set operations", () => {
+    const lhsData = `
+      @prefix ex: <http://ex.org/>.
+      @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+      ex:a ex:p "hello"@EN-us, "2020-01-01T10:00:00+01:00"^^xsd:dateTime, "042"^^xsd:short .
+    `;
+    const rhsData = `
+      @prefix ex: <http://ex.org/>.
+      @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+      ex:a ex:p "hello"@en-US, "2020-01-01T09:00:00Z"^^xsd:dateTime, "42"^^xsd:short .
+    `;
+    assert.equal(0, intersection({ lhsData, rhsData }).length);
+    assert.equal(3, intersection({ lhsData, rhsData, canonicalizeLiterals: true }).length);
+    assert.equal(0, difference({ lhsData, rhsData, canonicalizeLiterals: true }).length);
+  });
+
+  it("should keep the document prefixes for filters", () => {
+    const lhsData = `
+      @prefix ex: <http://ex.org/>.
+      # @prefix ex: <http://wrong.org/>.
+      ex:a ex:p "@prefix ex: <http://wrong.org/> ."; ex:q "042"^^<http://www.w3.org/2001/XMLSchema#short> .
+      ex:b ex:p "x" .
+    `;
+    const triples = statements({ lhsData, subject: "ex:a", canonicalizeLiterals: true });
+    assert.deepEqual(["http://ex.org/p", "http://ex.org/q"], triples.map((t) => t.predicate.value));
+    assert.equal("42", triples[1].object.value);
+  });
+});
+
+describe("IRI validation", () => {
+  const invalid = `
+    <http://ex.org/a b> <http://ex.org/p> <rel> .
+    <http://ex.org/ok> <http://ex.org/p> "1"^^<http://ex.org/%zz> .
+    <urn:isbn:123> <http://ex.org/p> <mailto:alice@ex.org> .
+  `;
+
+  it("should report invalid iris", () => {
+    assert.deepEqual([
+      [0, "http://ex.org/a b", 'invalid character " "'],
+      [0, "rel", "not an absolute iri"],
+      [1, "http://ex.org/%zz", "invalid percent-encoding"],
+    ], checkIris({ lhsData: invalid }).map((r) => [r.position, r.iri, r.message]));
+  });
+
+  it("should reject invalid iris in strict mode", () => {
+    assert.equal(3, statements({ lhsData: invalid }).length);
+    assert.throws(() => statements({ lhsData: invalid, iriMode: "strict" }), /invalid character/);
+    assert.throws(() => statements({ lhsData: invalid, iriMode: "unknown" }), /unknown iriMode/);
+  });
+
+  it("should keep the document prefixes for filters", () => {
+    const lhsData = `@prefix ex: <http://ex.org/>. ex:a ex:p ex:b . ex:b ex:p ex:a .`;
+    for (const iriMode of ["strict", "normalize"]) {
+      assert.deepEqual(["http://ex.org/b"], statements({ lhsData, subject: "ex:a", iriMode }).map((t) => t.object.value));
+    }
+  });
+
+  it("should normalize iris", () => {
+    const lhsData = `<HTTP://EX.org:8080/a/./b/../c%7e%2f?q=%3a#Frag> <http://ex.org/p> <http://ex.org/é> .`;
+    const [triple] = statements({ lhsData, iriMode: "normalize" });
+    assert.equal("http://ex.org:8080/a/c~%2F?q=%3A#Frag", triple.subject.value);
+    assert.equal("http://ex.org/é", triple.object.value);
+    assert.equal(1, intersection({
+      lhsData,
+      rhsData: `<http://ex.org:8080/a/c~%2F?q=%3A#Frag> <http://ex.org/p> <http://ex.org/é> .`,
+      iriMode: "normalize",
+    }).length);
+  });
+
+  it("should normalize non-ascii paths", () => {
+    const [triple] = statements({ lhsData: `<urn:é> <http://ex.org/p> <urn:é/./ü/../x> .`, iriMode: "normalize" });
+    assert.equal("urn:é", triple.subject.value);
+    assert.equal("urn:é/x", triple.object.value);
+  });
+});
+
+describe("Base IRI", () => {
+  const show = (triples) => triples.map((t) => [t.subject.value, t.predicate.value, t.object.value]);
+
+  it("should resolve relative iris against the base", () => {
+    assert.deepEqual([
+      ["http://ex.org/a/b/rel", "http://ex.org/a/b/p", "http://ex.org/a/up?x#f"],
+      ["http://other.org/x", "http://ex.org/a/b/c?q", "http://ex.org/a/b/c"],
+    ], show(statements({
+      lhsData: `<rel> <p> <../up?x#f> . <//other.org/x> <?q> <> .`,
+      baseIri: "http://ex.org/a/b/c",
+    })));
+    assert.deepEqual([["http://l.org/rel", "http://l.org/p", "x"]], show(statements({
+      lhsData: `<rel> <p> "x" .`,
+      lhsBaseIri: "http://l.org/",
+      baseIri: "http://ex.org/",
+    })));
+    assert.throws(() => statements({ lhsData: `<a> <b> <c> .`, baseIri: "rel" }), /invalid base iri/);
+  });
+
+  it("should resolve non-ascii references", () => {
+    assert.deepEqual([["urn:é", "http://ex.org/ü/p", "http://ex.org/é"]], show(statements({
+      lhsData: `<urn:é> <p> <../é> .`,
+      baseIri: "http://ex.org/ü/",
+    })));
+  });
+
+  it("should default the base to the file url", () => {
+    const lhsPath = "/tmp/base dir/doc.ttl";
+    require('fs').mkdirSync("/tmp/base dir", { recursive: true });
+    require('fs').writeFileSync(lhsPath, `<rel> <http://ex.org/p> <#frag> .`);
+    assert.deepEqual([
+      ["file:///tmp/base%20dir/rel", "http://ex.org/p", "file:///tmp/base%20dir/doc.ttl#frag"],
+    ], show(statements({ lhsPath })));
+  });
+
+  it("should keep the prefixes of a file with relative iris", () => {
+    const lhsPath = "/tmp/base dir/prefixed.ttl";
+    require('fs').mkdirSync("/tmp/base dir", { recursive: true });
+    require('fs').writeFileSync(lhsPath, `@prefix ex: <http://ex.org/>. <rel> ex:p ex:o . <rel> ex:q ex:o .`);
+    assert.deepEqual([
+      ["file:///tmp/base%20dir/rel", "http://ex.org/p", "http://ex.org/o"],
+    ], show(statements({ lhsPath, predicate: "ex:p" })));
+  });
+
+  it("should use a base per side", () => {
+    assert.equal(1, difference({
+      lhsData: `<a> <b> <c> .`,
+      rhsData: `<a> <b> <c> .`,
+      baseIri: "http://ex.org/",
+      rhsBaseIri: "http://other.org/",
+    }).length);
+  });
+
+  it("should relativize the n3 output", () => {
+    const n3 = statements({
+      lhsData: `<http://ex.org/a/rel> <http://ex.org/a/p> <http://ex.org/a/>, <http://ex.org/a/#f>, <http://other.org/z> .`,
+      baseIri: "http://ex.org/a/",
+      relativizeOutput: true,
+      outputType: "n3",
+    });
+    assert.ok(n3.startsWith("@base <http://ex.org/a/> ."));
+    assert.ok(n3.includes("<rel> <p> <>."));
+    assert.ok(n3.includes("<rel> <p> <#f>."));
+    assert.ok(n3.includes("<rel> <p> <http://other.org/z>."));
+  });
+
+  it("should relativize the n3 output written to a file", () => {
+    const params = {
+      lhsData: `<http://ex.org/a/rel> <http://ex.org/a/p> <http://ex.org/a/> .`,
+      baseIri: "http://ex.org/a/",
+      relativizeOutput: true,
+      outputType: "n3",
+    };
+    for (const compactIris of [false, true]) {
+      assert.equal(true, statements({ ...params, compactIris, outputFilePath: "/tmp/relative.ttl" }));
+      assert.equal(statements({ ...params, compactIris }), require('fs').readFileSync("/tmp/relative.ttl", "utf8"));
+    }
+  });
+});
+
+describe("Compact IRIs", () => {
+  const lhsData = `
+    @prefix foaf: <http://xmlns.com/foaf/0.1/>.
+    @prefix ex: <http://ex.org/>.
+    @prefix exd: <http://ex.org/deep/>.
+    ex:alice foaf:age "3"^^<http://www.w3.org/2001/XMLSchema#int> ; foaf:knows <http://ex.org/deep/bob>, <http://ex.org/a/b>, <http://other.org/x> .
+  `;
+  const extraPrefixes = { xsd: "http://www.w3.org/2001/XMLSchema#" };
+
+  it("should add compact names on js nodes", () => {
+    const triples = statements({ lhsData, compactIris: true, extraPrefixes });
+    assert.equal("ex:alice", triples[0].subject.compact);
+    assert.equal("foaf:age", triples[0].predicate.compact);
+    assert.equal(undefined, triples[0].object.compact);
+    assert.deepEqual(["exd:bob", undefined, undefined], triples.slice(1).map((t) => t.object.compact));
+    assert.equal(undefined, statements({ lhsData })[0].subject.compact);
+  });
+
+  it("should write prefixed names in n3", () => {
+    const n3 = statements({ lhsData, compactIris: true, extraPrefixes, outputType: "n3" });
+    assert.ok(n3.includes("@prefix xsd: <http://www.w3.org/2001/XMLSchema#> ."));
+    assert.ok(n3.includes(`ex:alice foaf:age "3"^^xsd:int .`));
+    assert.ok(n3.includes("ex:alice foaf:knows exd:bob ."));
+    assert.ok(n3.includes("ex:alice foaf:knows <http://other.org/x> ."));
+    const outputFilePath = "/tmp/compact.ttl";
+    assert.equal(true, statements({ lhsData, compactIris: true, outputType: "n3", outputFilePath }));
+    assert.ok(require('fs').readFileSync(outputFilePath, "utf8").includes("ex:alice foaf:knows exd:bob ."));
+  });
+
+  it("should write n3 that parses back to the same model", () => {
+    const lhsData = `
+      @prefix ex: <http://ex.org/>.
+      <http://ex.org/x.y> <http://ex.org/a~b> <http://ex.org/-x>, <http://ex.org/v1:a%20b>, <http://ex.org/c_d-e> .
+    `;
+    const n3 = statements({ lhsData, compactIris: true, outputType: "n3" });
+    assert.ok(n3.includes("<http://ex.org/x.y> <http://ex.org/a~b> <http://ex.org/-x> ."));
+    assert.ok(n3.includes("ex:c_d-e ."));
+    assert.equal(0, difference({ lhsData, rhsData: n3 }).length);
+    assert.equal(0, difference({ lhsData: n3, rhsData: lhsData }).length);
+  });
+});